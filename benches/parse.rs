@@ -0,0 +1,71 @@
+//! Parse-and-drop throughput of the default (`Rc`-per-node) parser vs. the
+//! arena parser, on a multi-megabyte generated data file.
+//!
+//! Run with `cargo bench --bench parse`.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use lisp_playground::parser::{AstArena, parse_text};
+
+const INPUT_BYTES: usize = 4 * 1024 * 1024;
+const ITERATIONS: usize = 10;
+
+/// Something shaped like a data file: lots of small, nested records
+fn sample_input(target_bytes: usize) -> String {
+    let mut input = String::from("(");
+    let mut row = 0;
+    while input.len() < target_bytes {
+        input.push_str(&format!(
+            "(row {row} -{row}.5 \"name_{row}\" :key_{row} (tags a b c) (point \
+             (x {row}) (y - 3)))\n"
+        ));
+        row += 1;
+    }
+    input.push(')');
+    input
+}
+
+/// Time `f` over several iterations, returning the fastest run
+fn fastest(mut f: impl FnMut()) -> Duration {
+    (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn report(name: &str, n_bytes: usize, elapsed: Duration) {
+    let mb = n_bytes as f64 / (1024. * 1024.);
+    println!(
+        "{name:>8}: {:>8.2?} ({:.1} MiB/s)",
+        elapsed,
+        mb / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    let input = sample_input(INPUT_BYTES);
+    println!(
+        "parse + drop, {:.1} MiB input, best of {ITERATIONS}",
+        input.len() as f64 / (1024. * 1024.)
+    );
+
+    let rc = fastest(|| {
+        let parsed = parse_text(black_box(&input)).unwrap();
+        drop(black_box(parsed));
+    });
+    report("rc", input.len(), rc);
+
+    let arena = fastest(|| {
+        let mut arena = AstArena::new();
+        let root = arena
+            .parse_text(black_box(&input))
+            .unwrap();
+        black_box(root);
+        drop(black_box(arena));
+    });
+    report("arena", input.len(), arena);
+}
@@ -0,0 +1,140 @@
+//! `#[derive(FromLisp, IntoLisp)]` for structs with named fields, which
+//! convert to and from lisp records: each field maps to the record key of the
+//! same name, or to the name given with `#[lisp(rename = "...")]`. Generic
+//! structs get a `FromLisp`/`IntoLisp` bound on each type parameter.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    Data, DeriveInput, Fields, Generics, Ident, LitStr, parse_macro_input,
+    parse_quote,
+};
+
+#[proc_macro_derive(FromLisp, attributes(lisp))]
+pub fn derive_from_lisp(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_lisp(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(IntoLisp, attributes(lisp))]
+pub fn derive_into_lisp(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_into_lisp(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_from_lisp(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let generics = with_bound(
+        &input.generics,
+        parse_quote!(::lisp_playground::ast::FromLisp),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let (idents, keys): (Vec<_>, Vec<_>) = record_fields(input)?
+        .into_iter()
+        .unzip();
+
+    Ok(quote! {
+        impl #impl_generics ::lisp_playground::ast::FromLisp
+            for #name #ty_generics #where_clause
+        {
+            fn from_lisp(
+                var: &::lisp_playground::ast::Var,
+            ) -> ::lisp_playground::EResult<Self> {
+                let record = ::lisp_playground::ast::expect_record(var)?;
+                Ok(#name {
+                    #(
+                        #idents: ::lisp_playground::ast::record_field(
+                            record,
+                            #keys,
+                        )?,
+                    )*
+                })
+            }
+        }
+    })
+}
+
+fn expand_into_lisp(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let generics = with_bound(
+        &input.generics,
+        parse_quote!(::lisp_playground::ast::IntoLisp),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let (idents, keys): (Vec<_>, Vec<_>) = record_fields(input)?
+        .into_iter()
+        .unzip();
+
+    Ok(quote! {
+        impl #impl_generics ::lisp_playground::ast::IntoLisp
+            for #name #ty_generics #where_clause
+        {
+            fn into_lisp(self) -> ::lisp_playground::ast::Var {
+                let mut record = ::lisp_playground::ast::Mapping::new();
+                #(
+                    record.insert(
+                        #keys.to_string(),
+                        ::lisp_playground::ast::IntoLisp::into_lisp(
+                            self.#idents,
+                        ),
+                    );
+                )*
+                ::lisp_playground::ast::Expr::Record(record).into()
+            }
+        }
+    })
+}
+
+/// `generics`, with `bound` added to each type parameter
+fn with_bound(generics: &Generics, bound: syn::TypeParamBound) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(bound.clone());
+    }
+    generics
+}
+
+/// Each field, with its record key
+fn record_fields(input: &DeriveInput) -> syn::Result<Vec<(Ident, String)>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "only structs can be converted to and from lisp records",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "only structs with named fields can be converted to and from lisp \
+             records",
+        ));
+    };
+
+    fields
+        .named
+        .iter()
+        .map(|field| {
+            let ident = field.ident.clone().unwrap();
+            let mut key = ident.to_string();
+            for attr in field.attrs.iter() {
+                if !attr.path().is_ident("lisp") {
+                    continue;
+                }
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename") {
+                        key = meta.value()?.parse::<LitStr>()?.value();
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `rename = \"...\"`"))
+                    }
+                })?;
+            }
+            Ok((ident, key))
+        })
+        .collect()
+}
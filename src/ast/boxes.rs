@@ -0,0 +1,47 @@
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+use super::Var;
+
+/// A mutable reference cell, made with `(box value)`.
+///
+/// Everything else is immutable, and closures capture copies of plain
+/// values, so boxes are the way to share mutable state: a closure that
+/// captures a box captures the box itself, and sees (and makes) changes to
+/// its contents.
+#[derive(Debug)]
+pub struct MutableBox {
+    value: RefCell<Var>,
+}
+
+impl MutableBox {
+    pub fn new(value: Var) -> Rc<Self> {
+        Rc::new(MutableBox {
+            value: RefCell::new(value),
+        })
+    }
+
+    pub fn get(&self) -> Var {
+        self.value.borrow().clone()
+    }
+
+    /// Set a new value, returning the old one
+    pub fn replace(&self, value: Var) -> Var {
+        self.value.replace(value)
+    }
+}
+
+/// Boxes are compared by identity: two boxes with equal contents are still
+/// different boxes
+impl PartialEq for MutableBox {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Display for MutableBox {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#box[{}]", self.value.borrow())
+    }
+}
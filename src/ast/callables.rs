@@ -1,27 +1,42 @@
-use std::fmt::{Display, Formatter};
-use std::iter::repeat;
+use std::fmt::{Debug, Display, Formatter};
+use std::iter::repeat_n;
+use std::rc::Rc;
 
-use super::{EResult, OwnedSExpr, SExpr};
-use crate::Scope;
+use itertools::Itertools;
+
+use super::{
+    EResult, EvalError, Expr, HostFn, Mapping, OwnedSExpr, Pattern, SExpr,
+    SyntaxRules,
+};
 use crate::ast::variables::Var;
+use crate::{Context, Scope};
 
 /*****************\
 |* Special forms *|
 \*****************/
 #[derive(Debug, Clone, PartialEq)]
+#[allow(unpredictable_function_pointer_comparisons)]
 pub struct SpecialForm {
     pub name: String,
     pub arity: Arity,
+    pub doc: &'static str,
 
     /// Evaluate the special form
-    pub eval: fn(&SExpr, &mut Scope) -> EResult<Var>,
+    pub eval: fn(&SExpr, &mut Scope, &mut Context) -> EResult<Var>,
 
     /// given the s-expressions arguments, return a list of
     /// variables that it needs from its enclosing scope.
     /// This method is, in particular, a hook for `lambda`
     /// (or anything building a closure) to call on any interior special forms.
     /// Q: is this enough? Do we need a real scope object?
-    pub bind_outer_scope: fn(&SExpr, &Scope, &mut Scope) -> EResult<()>,
+    pub bind_outer_scope:
+        fn(&SExpr, &Scope, &mut Scope, &mut Context) -> EResult<()>,
+
+    /// Like `bind_outer_scope`, for a call made directly in a body: the second
+    /// argument is the body's own analysis scope, where definitions declare
+    /// the names they bind
+    pub bind_body_outer_scope:
+        fn(&SExpr, &mut Scope, &mut Scope, &mut Context) -> EResult<()>,
 }
 
 impl Display for SpecialForm {
@@ -30,6 +45,37 @@ impl Display for SpecialForm {
     }
 }
 
+/**********\
+|* Macros *|
+\**********/
+/// A special form defined by the user, with `defmacro` or `define-syntax`:
+/// computes the form to evaluate instead from the (unevaluated) argument
+/// forms.
+///
+/// Macros follow the same rules as builtin special forms (they can't be
+/// aliased, shadowed, passed to or returned from functions), so closure
+/// capture analysis can expand them too.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Macro {
+    pub name: String,
+    pub expander: MacroExpander,
+    pub meta: Rc<Metadata>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacroExpander {
+    /// `defmacro`: a function of the argument forms
+    Procedure(Function),
+    /// `define-syntax`: hygienic pattern-based rules
+    Rules(Rc<SyntaxRules>),
+}
+
+impl Display for Macro {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#macro[{}]", self.name)
+    }
+}
+
 /*************\
 |* Functions *|
 \*************/
@@ -37,40 +83,429 @@ impl Display for SpecialForm {
 pub struct Function {
     pub name: String,
     pub arity: Arity,
-    pub arguments: Vec<String>,
+    pub params: Parameters,
     pub form: CallForm,
-    // TODO: add metadata (maybe arbitrary like clojure,
-    //    maybe also some fixed fields like __doc__, __module__, etc)
+    pub meta: Rc<Metadata>,
+}
+
+/// Documentation and provenance of a function or macro, see `doc`, `source`
+/// and `arglist`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    /// The docstring: `(define (f x) 'Does things.' body...)`
+    pub doc: Option<String>,
+    /// Where the top-level form that defined it starts
+    pub origin: Option<Origin>,
+    /// The `lambda`, `define`... form that defined it
+    pub source: Option<Var>,
+    /// The name of the function it was defined in, if any
+    pub parent: Option<String>,
+}
+
+impl Metadata {
+    pub fn with_doc(doc: &str) -> Rc<Self> {
+        Rc::new(Metadata {
+            doc: Some(doc.to_string()),
+            ..Default::default()
+        })
+    }
+}
+
+/// A place in the source code: a file (or `<string>`, `<repl>`...) and line
+#[derive(Debug, Clone, PartialEq)]
+pub struct Origin {
+    pub file: String,
+    pub line: usize,
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
 }
 
 impl Display for Function {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} {}[{}]",
-            self.form.type_str(),
-            self.name,
-            self.arguments.join(",")
-        )
+        write!(f, "{} {}[", self.form.type_str(), self.name)?;
+        match &self.form {
+            // `partial add[x=1](y)`: the bound arguments, then the rest
+            CallForm::Curry {
+                inner,
+                bound,
+                keywords,
+                ..
+            } => {
+                let labels = inner.params.leading_labels();
+                let bound = bound
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arg)| {
+                        match labels.get(i) {
+                            Some(label) => format!("{label}={arg}"),
+                            None => arg.to_string(),
+                        }
+                    })
+                    .chain(
+                        keywords
+                            .chunks(2)
+                            .map(|pair| pair.iter().join("=")),
+                    );
+                return write!(f, "{}]({})", bound.format(","), self.params);
+            },
+            CallForm::Overloaded(clauses) => {
+                write!(
+                    f,
+                    "{}",
+                    clauses
+                        .iter()
+                        .format_with(" | ", |clause, f| f(&clause.params))
+                )?
+            },
+            _ => write!(f, "{}", self.params)?,
+        }
+        write!(f, "]")
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// A host (Rust) closure callable from lisp, see `Function::native`
+pub type NativeFn = Rc<dyn Fn(&mut Context, &SExpr) -> EResult<Var>>;
+
+#[derive(Clone)]
 pub enum CallForm {
-    Lambda { sexpr: OwnedSExpr, scope: Scope },
-    Builtin(fn(&SExpr) -> EResult<Var>),
-    // Curry({inner: Rc<CallForm>,
-    //       bound: Scope}),
+    /// Evaluates each `body` form in turn, returning the last result
+    Lambda {
+        body: OwnedSExpr,
+        scope: Scope,
+        /// Names bound at call time, for recursion (see `RecursiveGroup`)
+        group: Option<Rc<RecursiveGroup>>,
+    },
+    Builtin(fn(&SExpr, &mut Context) -> EResult<Var>),
+    /// A closure provided by the host application, which may hold state
+    Native(NativeFn),
+    /// Multi-arity function: calls dispatch to the first clause whose arity
+    /// matches the arguments
+    Overloaded(Vec<Function>),
+    /// Partial application: calls `inner` with the `bound` arguments first
+    Curry {
+        inner: Rc<Function>,
+        /// Leading positional arguments
+        bound: Rc<[Var]>,
+        /// `:label value` keyword arguments, passed after the positional ones
+        /// (so the caller's keyword arguments win)
+        keywords: Rc<[Var]>,
+        /// Made by `curry`: calls with too few arguments bind them too,
+        /// instead of failing
+        auto: bool,
+    },
 }
 
 impl CallForm {
     fn type_str(&self) -> &'static str {
         match self {
-            CallForm::Lambda { .. } => "λ",
+            CallForm::Lambda { .. } | CallForm::Overloaded(..) => "λ",
             CallForm::Builtin(..) => "builtin",
-            // CallForm::Curry(_) => "curry",
+            CallForm::Native(..) => "native",
+            CallForm::Curry { auto: false, .. } => "partial",
+            CallForm::Curry { auto: true, .. } => "curry",
+        }
+    }
+}
+
+impl Debug for CallForm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallForm::Lambda { body, scope, group } => {
+                f.debug_struct("Lambda")
+                    .field("body", body)
+                    .field("scope", scope)
+                    .field("group", group)
+                    .finish()
+            },
+            CallForm::Builtin(func) => {
+                f.debug_tuple("Builtin")
+                    .field(func)
+                    .finish()
+            },
+            CallForm::Native(func) => {
+                f.debug_tuple("Native")
+                    .field(&Rc::as_ptr(func))
+                    .finish()
+            },
+            CallForm::Overloaded(clauses) => {
+                f.debug_tuple("Overloaded")
+                    .field(clauses)
+                    .finish()
+            },
+            CallForm::Curry {
+                inner,
+                bound,
+                keywords,
+                auto,
+            } => {
+                f.debug_struct("Curry")
+                    .field("inner", inner)
+                    .field("bound", bound)
+                    .field("keywords", keywords)
+                    .field("auto", auto)
+                    .finish()
+            },
+        }
+    }
+}
+
+/// Builtins and native closures are compared by identity
+impl PartialEq for CallForm {
+    #[allow(unpredictable_function_pointer_comparisons)]
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                CallForm::Lambda { body, scope, group },
+                CallForm::Lambda {
+                    body: other_body,
+                    scope: other_scope,
+                    group: other_group,
+                },
+            ) => {
+                body == other_body && scope == other_scope && group == other_group
+            },
+            (CallForm::Builtin(f), CallForm::Builtin(g)) => f == g,
+            (CallForm::Native(f), CallForm::Native(g)) => Rc::ptr_eq(f, g),
+            (CallForm::Overloaded(f), CallForm::Overloaded(g)) => f == g,
+            (
+                CallForm::Curry {
+                    inner,
+                    bound,
+                    keywords,
+                    auto,
+                },
+                CallForm::Curry {
+                    inner: other_inner,
+                    bound: other_bound,
+                    keywords: other_keywords,
+                    auto: other_auto,
+                },
+            ) => {
+                inner == other_inner
+                    && bound == other_bound
+                    && keywords == other_keywords
+                    && auto == other_auto
+            },
+            _ => false,
+        }
+    }
+}
+
+impl Function {
+    /// A function that calls the host closure `f` with the (evaluated)
+    /// arguments, e.g. to give lisp code access to a database handle.
+    /// The arity is checked before `f` is called.
+    pub fn native(
+        name: &str,
+        arguments: &[&str],
+        arity: Arity,
+        f: impl Fn(&mut Context, &SExpr) -> EResult<Var> + 'static,
+    ) -> Function {
+        Function {
+            name: name.to_string(),
+            arity,
+            params: Parameters::positional(
+                arguments
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            ),
+            form: CallForm::Native(Rc::new(f)),
+            meta: Rc::default(),
+        }
+    }
+
+    /// A function that calls the Rust function `f`, converting its arguments
+    /// from lisp values and its result back (see `FromLisp`/`IntoLisp`):
+    ///     `Function::host("add", &["x", "y"], |x: isize, y: isize| x + y)`
+    /// A mistyped argument is a type error.
+    pub fn host<Args, F: HostFn<Args> + 'static>(
+        name: &str,
+        arguments: &[&str],
+        f: F,
+    ) -> Function {
+        let arity = Arity::Fixed(F::num_args());
+        Function::native(name, arguments, arity, move |_, args| {
+            f.call_with(args)
+        })
+    }
+
+    /// This function with its leading arguments bound to `args`, e.g.
+    /// `(partial add 1)`, and its keyword arguments to the keyword arguments
+    /// in `args`. Fails if it can't take that many positional arguments.
+    pub fn partial(&self, args: &SExpr) -> EResult<Function> {
+        self.bind_leading(args, false)
+    }
+
+    /// This function, auto-curried: calling it with too few arguments binds
+    /// them, and it's called once it has enough, e.g. `((curry add) 1)` is
+    /// `(partial add 1)`
+    pub fn curry(&self) -> EResult<Function> {
+        self.bind_leading(&[], true)
+    }
+
+    fn bind_leading(&self, args: &SExpr, auto: bool) -> EResult<Function> {
+        let (positional, keyword_args) =
+            args.split_at(self.params.keyword_args_start(args));
+        // partials of partials bind all their arguments at once
+        let (inner, bound, keywords, auto) = match &self.form {
+            CallForm::Curry {
+                inner,
+                bound,
+                keywords,
+                auto: inner_auto,
+            } => {
+                let bound: Rc<[Var]> = bound
+                    .iter()
+                    .chain(positional)
+                    .cloned()
+                    .collect();
+                let keywords: Rc<[Var]> = keywords
+                    .iter()
+                    .chain(keyword_args)
+                    .cloned()
+                    .collect();
+                (
+                    inner.clone(),
+                    bound,
+                    keywords,
+                    auto || *inner_auto,
+                )
+            },
+            _ => {
+                (
+                    Rc::new(self.clone()),
+                    Rc::from(positional),
+                    Rc::from(keyword_args),
+                    auto,
+                )
+            },
+        };
+
+        let arity = inner
+            .arity
+            .after(bound.len())
+            .ok_or_else(|| {
+                EvalError::Arity {
+                    name: inner.name.clone(),
+                    arity: inner.arity.clone(),
+                    num_args_provided: bound.len(),
+                }
+            })?;
+        Ok(Function {
+            name: inner.name.clone(),
+            arity,
+            params: inner.params.after(bound.len()),
+            meta: inner.meta.clone(),
+            form: CallForm::Curry {
+                inner,
+                bound,
+                keywords,
+                auto,
+            },
+        })
+    }
+
+    /// The values this function captured from its enclosing scope when it was
+    /// created (`None` for builtins and native functions). Special forms it
+    /// calls are left out.
+    ///
+    /// Names from its `RecursiveGroup` aren't captured: they're bound on each
+    /// call.
+    pub fn captures(&self) -> Option<Mapping> {
+        match &self.form {
+            CallForm::Lambda { scope, .. } => Some(scope.values()),
+            // all clauses share one capture scope
+            CallForm::Overloaded(clauses) => {
+                clauses
+                    .first()
+                    .and_then(Function::captures)
+            },
+            CallForm::Curry { inner, .. } => inner.captures(),
+            CallForm::Builtin(_) | CallForm::Native(_) => None,
+        }
+    }
+
+    /// A copy of this function that binds `group` whenever it's called
+    pub fn with_group(&self, group: &Rc<RecursiveGroup>) -> Function {
+        let form = match &self.form {
+            CallForm::Lambda { body, scope, .. } => {
+                CallForm::Lambda {
+                    body: body.clone(),
+                    scope: scope.clone(),
+                    group: Some(group.clone()),
+                }
+            },
+            CallForm::Overloaded(clauses) => {
+                CallForm::Overloaded(
+                    clauses
+                        .iter()
+                        .map(|clause| clause.with_group(group))
+                        .collect(),
+                )
+            },
+            CallForm::Curry {
+                inner,
+                bound,
+                keywords,
+                auto,
+            } => {
+                CallForm::Curry {
+                    inner: Rc::new(inner.with_group(group)),
+                    bound: bound.clone(),
+                    keywords: keywords.clone(),
+                    auto: *auto,
+                }
+            },
+            CallForm::Builtin(_) | CallForm::Native(_) => self.form.clone(),
+        };
+        Function {
+            form,
+            ..self.clone()
+        }
+    }
+}
+
+/// Definitions that may refer to each other (or themselves) by name, e.g. a
+/// recursive `define` or the bindings of a `letrec`.
+///
+/// Greedy binding can't capture these, since they don't exist yet when their
+/// bodies are analyzed. Instead, the group keeps the values as they were
+/// defined ("templates"), and every call to one of its functions binds all of
+/// them in the call's scope.
+///
+/// Templates never refer to the group itself, so this doesn't create `Rc`
+/// cycles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecursiveGroup {
+    members: Vec<(String, Var)>,
+}
+
+impl RecursiveGroup {
+    pub fn new(members: Vec<(String, Var)>) -> Rc<Self> {
+        Rc::new(RecursiveGroup { members })
+    }
+
+    /// The member `template` as seen from outside: functions call back into
+    /// the group, while any other values are unchanged.
+    pub fn attach(self: &Rc<Self>, template: &Var) -> Var {
+        match template.as_ref() {
+            Expr::Function(func) => func.with_group(self).into(),
+            _ => template.clone(),
         }
     }
+
+    /// Bind every member in `scope`
+    pub fn bind(self: &Rc<Self>, scope: &mut Scope) -> EResult<()> {
+        for (name, template) in self.members.iter() {
+            scope.set(name, self.attach(template))?;
+        }
+        Ok(())
+    }
 }
 
 /*********************\
@@ -79,7 +514,93 @@ impl CallForm {
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Arity {
     Fixed(usize),
+    AtLeast(usize),
+    Between(usize, usize),
     Variadic,
+    /// Any of several arities (for multi-arity functions)
+    OneOf(Vec<Arity>),
+}
+
+impl Arity {
+    /// The fewest arguments this accepts
+    pub fn min(&self) -> usize {
+        match self {
+            Arity::Fixed(n) | Arity::AtLeast(n) | Arity::Between(n, _) => *n,
+            Arity::Variadic => 0,
+            Arity::OneOf(arities) => {
+                arities
+                    .iter()
+                    .map(Arity::min)
+                    .min()
+                    .unwrap_or(0)
+            },
+        }
+    }
+
+    /// The arity of what's left once `n` arguments are bound, or `None` if
+    /// this can't take `n` arguments or more
+    pub fn after(&self, n: usize) -> Option<Arity> {
+        match self {
+            Arity::Fixed(k) => Some(Arity::Fixed(k.checked_sub(n)?)),
+            Arity::AtLeast(k) => Some(Arity::AtLeast(k.saturating_sub(n))),
+            Arity::Between(min, max) => {
+                let max = max.checked_sub(n)?;
+                match min.saturating_sub(n) {
+                    min if min == max => Some(Arity::Fixed(max)),
+                    min => Some(Arity::Between(min, max)),
+                }
+            },
+            Arity::Variadic => Some(Arity::Variadic),
+            Arity::OneOf(arities) => {
+                let arities: Vec<Arity> = arities
+                    .iter()
+                    .filter_map(|arity| arity.after(n))
+                    .collect();
+                match arities.len() {
+                    0 => None,
+                    1 => arities.into_iter().next(),
+                    _ => Some(Arity::OneOf(arities)),
+                }
+            },
+        }
+    }
+
+    pub fn accepts(&self, n_args: usize) -> bool {
+        match self {
+            Arity::Fixed(n) => n_args == *n,
+            Arity::AtLeast(n) => n_args >= *n,
+            Arity::Between(min, max) => (*min..=*max).contains(&n_args),
+            Arity::Variadic => true,
+            Arity::OneOf(arities) => {
+                arities
+                    .iter()
+                    .any(|a| a.accepts(n_args))
+            },
+        }
+    }
+
+    /// How many arguments this accepts, in words (for error messages)
+    pub fn describe(&self) -> String {
+        match self {
+            Arity::Fixed(n) => n.to_string(),
+            Arity::AtLeast(n) => format!("at least {n}"),
+            Arity::Between(min, max) => format!("{min} to {max}"),
+            Arity::Variadic => "any number of".to_string(),
+            Arity::OneOf(arities) => {
+                let mut descriptions = arities
+                    .iter()
+                    .map(Arity::describe)
+                    .collect::<Vec<_>>();
+                match descriptions.pop() {
+                    None => "no".to_string(),
+                    Some(last) if descriptions.is_empty() => last,
+                    Some(last) => {
+                        format!("{} or {last}", descriptions.join(", "))
+                    },
+                }
+            },
+        }
+    }
 }
 
 impl Display for Arity {
@@ -89,8 +610,27 @@ impl Display for Arity {
                 write!(
                     f,
                     "({})",
-                    repeat("_")
-                        .take(*n)
+                    repeat_n("_", *n)
+                        .collect::<Vec<&str>>()
+                        .join(",")
+                )
+            },
+            Arity::AtLeast(n) => {
+                write!(
+                    f,
+                    "({})",
+                    repeat_n("_", *n)
+                        .chain(["..."])
+                        .collect::<Vec<&str>>()
+                        .join(",")
+                )
+            },
+            Arity::Between(min, max) => {
+                write!(
+                    f,
+                    "({})",
+                    repeat_n("_", *min)
+                        .chain(repeat_n("_?", max - min))
                         .collect::<Vec<&str>>()
                         .join(",")
                 )
@@ -98,6 +638,229 @@ impl Display for Arity {
             Arity::Variadic => {
                 write!(f, "(...)")
             },
+            Arity::OneOf(arities) => {
+                write!(f, "{}", arities.iter().format(" | "))
+            },
+        }
+    }
+}
+
+/// The parameter list of a function. In a lambda list, these are written
+/// `(a b &opt c (d 1) & rest &key (:label e 2))`:
+/// - `positional` arguments are required, and may be destructured (see
+///   `Pattern`);
+/// - `optional` arguments may be omitted, falling back to their defaults;
+/// - extra positional arguments are collected into a list bound to `rest`;
+/// - `keyword` arguments are passed as `:label value` after all positional
+///   arguments.
+///
+/// Defaults are expressions evaluated at call time, after binding all
+/// previous parameters (so they may refer to them).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Parameters {
+    pub positional: Vec<Pattern>,
+    pub optional: Vec<OptionalParam>,
+    pub rest: Option<String>,
+    pub keyword: Vec<KeywordParam>,
+}
+
+/// An optional positional parameter; `nil` if there's no default
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionalParam {
+    pub name: String,
+    pub default: Option<Var>,
+}
+
+/// A keyword parameter; it's required if there's no default.
+///
+/// The `label` is what callers use (`:label value`), while `name` is what
+/// it's bound to inside the function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeywordParam {
+    pub label: String,
+    pub name: String,
+    pub default: Option<Var>,
+}
+
+impl Parameters {
+    pub fn positional(names: Vec<String>) -> Self {
+        Parameters {
+            positional: names
+                .into_iter()
+                .map(Pattern::Symbol)
+                .collect(),
+            ..Default::default()
         }
     }
+
+    /// Arity, counting only positional arguments
+    pub fn arity(&self) -> Arity {
+        let required = self.positional.len();
+        match (&self.rest, self.optional.len()) {
+            (Some(_), _) => Arity::AtLeast(required),
+            (None, 0) => Arity::Fixed(required),
+            (None, n_optional) => Arity::Between(required, required + n_optional),
+        }
+    }
+
+    /// All names bound by these parameters, in binding order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.positional
+            .iter()
+            .flat_map(Pattern::names)
+            .chain(
+                self.optional
+                    .iter()
+                    .map(|p| p.name.as_str()),
+            )
+            .chain(self.rest.iter().map(String::as_str))
+            .chain(
+                self.keyword
+                    .iter()
+                    .map(|p| p.name.as_str()),
+            )
+    }
+
+    /// The parameters as they're written in a lambda list, e.g.
+    /// `(a &opt (b 1) & rest)`
+    pub fn lambda_list(&self) -> OwnedSExpr {
+        let symbol =
+            |name: &str| -> Var { Expr::Symbol(name.to_string()).into() };
+        let with_default = |name: &str, default: &Option<Var>| {
+            match default {
+                Some(default) => {
+                    Expr::SExpr(vec![symbol(name), default.clone()]).into()
+                },
+                None => symbol(name),
+            }
+        };
+
+        let mut list: OwnedSExpr = self
+            .positional
+            .iter()
+            .map(Pattern::to_var)
+            .collect();
+        if !self.optional.is_empty() {
+            list.push(symbol("&opt"));
+            list.extend(
+                self.optional
+                    .iter()
+                    .map(|p| with_default(&p.name, &p.default)),
+            );
+        }
+        if let Some(rest) = &self.rest {
+            list.push(symbol("&"));
+            list.push(symbol(rest));
+        }
+        if !self.keyword.is_empty() {
+            list.push(symbol("&key"));
+            list.extend(self.keyword.iter().map(|p| {
+                if p.label == p.name {
+                    return with_default(&p.name, &p.default);
+                }
+                let mut param =
+                    vec![Expr::Keyword(p.label.clone()).into(), symbol(&p.name)];
+                param.extend(p.default.clone());
+                Expr::SExpr(param).into()
+            }));
+        }
+        list
+    }
+
+    /// What's left once `n` leading arguments are bound: they fill the
+    /// positional parameters, then the optional ones (the rest and keyword
+    /// parameters are kept)
+    pub fn after(&self, n: usize) -> Self {
+        let n_optional = n.saturating_sub(self.positional.len());
+        Parameters {
+            positional: self
+                .positional
+                .iter()
+                .skip(n)
+                .cloned()
+                .collect(),
+            optional: self
+                .optional
+                .iter()
+                .skip(n_optional)
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Labels for leading arguments: the positional parameters, then the
+    /// optional ones
+    fn leading_labels(&self) -> Vec<String> {
+        self.positional
+            .iter()
+            .map(Pattern::to_string)
+            .chain(
+                self.optional
+                    .iter()
+                    .map(|p| p.name.clone()),
+            )
+            .collect()
+    }
+
+    pub fn keyword_param(&self, label: &str) -> Option<&KeywordParam> {
+        self.keyword
+            .iter()
+            .find(|p| p.label == label)
+    }
+
+    /// Where a call's keyword arguments start: at the first keyword that
+    /// labels one of these keyword parameters (so functions without keyword
+    /// parameters can still receive keywords as ordinary values)
+    pub fn keyword_args_start(&self, args: &SExpr) -> usize {
+        let is_label = |var: &Var| {
+            matches!(
+                var.as_ref(),
+                Expr::Keyword(label) if self.keyword_param(label).is_some()
+            )
+        };
+        args.iter()
+            .position(is_label)
+            .unwrap_or(args.len())
+    }
+}
+
+impl Display for Parameters {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let positional = self
+            .positional
+            .iter()
+            .map(Pattern::to_string);
+        let optional = self.optional.iter().map(|p| {
+            match &p.default {
+                Some(default) => format!("[{}={default}]", p.name),
+                None => format!("[{}]", p.name),
+            }
+        });
+        let rest = self
+            .rest
+            .iter()
+            .map(|name| format!("&{name}"));
+        let keyword = self.keyword.iter().map(|p| {
+            let label = if p.label == p.name {
+                format!(":{}", p.label)
+            } else {
+                format!(":{}->{}", p.label, p.name)
+            };
+            match &p.default {
+                Some(default) => format!("{label}={default}"),
+                None => label,
+            }
+        });
+
+        write!(
+            f,
+            "{}",
+            positional
+                .chain(optional)
+                .chain(rest)
+                .chain(keyword)
+                .format(",")
+        )
+    }
 }
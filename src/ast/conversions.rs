@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+
+use super::{EResult, EvalError, Expr, Mapping, SExpr, Value, Var};
+
+/*******************************\
+|* Rust types <-> lisp values  *|
+\*******************************/
+// Typed conversions for embedding: unlike the `Value` conversions, these
+// cover lists, records and `nil`, and failures are ordinary type errors that
+// can be returned straight to lisp code.
+//
+// Structs convert to and from records with `#[derive(FromLisp, IntoLisp)]`:
+// each field maps to the record key of the same name (or the name given with
+// `#[lisp(rename = "...")]`).
+
+/// Rust values that can be built from a lisp value
+pub trait FromLisp: Sized {
+    fn from_lisp(var: &Var) -> EResult<Self>;
+}
+
+/// Rust values that can be turned into a lisp value
+pub trait IntoLisp {
+    fn into_lisp(self) -> Var;
+}
+
+/// What a host function returns: a value, or a fallible `EResult` of one
+pub trait IntoLispResult {
+    fn into_lisp_result(self) -> EResult<Var>;
+}
+
+impl<T: IntoLisp> IntoLispResult for T {
+    fn into_lisp_result(self) -> EResult<Var> {
+        Ok(self.into_lisp())
+    }
+}
+
+impl<T: IntoLisp> IntoLispResult for EResult<T> {
+    fn into_lisp_result(self) -> EResult<Var> {
+        self.map(IntoLisp::into_lisp)
+    }
+}
+
+/// The type error for a lisp value that isn't the `expected` type
+pub fn conversion_error(expected: &str, var: &Var) -> EvalError {
+    let actual = match var.as_ref() {
+        Expr::Value(value) => value.type_str(),
+        expr => expr.type_str(),
+    };
+    EvalError::Type {
+        expected: expected.to_string(),
+        actual: actual.to_string(),
+    }
+}
+
+//*** Lisp values themselves ***//
+impl FromLisp for Var {
+    fn from_lisp(var: &Var) -> EResult<Self> {
+        Ok(var.clone())
+    }
+}
+
+impl IntoLisp for Var {
+    fn into_lisp(self) -> Var {
+        self
+    }
+}
+
+impl IntoLisp for Expr {
+    fn into_lisp(self) -> Var {
+        self.into()
+    }
+}
+
+impl IntoLisp for Value {
+    fn into_lisp(self) -> Var {
+        self.into()
+    }
+}
+
+impl IntoLisp for () {
+    fn into_lisp(self) -> Var {
+        Value::Nil.into()
+    }
+}
+
+//*** Scalars ***//
+// The first element is the rust type, the second is the `Value` variant
+macro_rules! impl_scalar_conversions {
+    ($($t:ty, $v:ident);* $(;)?) => {
+        $(
+            impl FromLisp for $t {
+                fn from_lisp(var: &Var) -> EResult<Self> {
+                    match var.as_ref() {
+                        Expr::Value(Value::$v(native_val)) => {
+                            Ok(native_val.clone())
+                        },
+                        _ => Err(conversion_error(stringify!($v), var)),
+                    }
+                }
+            }
+
+            impl IntoLisp for $t {
+                fn into_lisp(self) -> Var {
+                    Value::$v(self).into()
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar_conversions! {
+    String, Str;
+    char, Char;
+    f64, Float;
+    isize, Int;
+    bool, Bool;
+}
+
+// Other integer types, checked against the range of the rust type. Values
+// out of the range of `isize` saturate on the way to lisp.
+macro_rules! impl_int_conversions {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl FromLisp for $t {
+                fn from_lisp(var: &Var) -> EResult<Self> {
+                    let int = isize::from_lisp(var)?;
+                    <$t>::try_from(int).map_err(|_| {
+                        EvalError::Type {
+                            expected: concat!(
+                                "Int in the range of ",
+                                stringify!($t)
+                            )
+                            .to_string(),
+                            actual: int.to_string(),
+                        }
+                    })
+                }
+            }
+
+            impl IntoLisp for $t {
+                fn into_lisp(self) -> Var {
+                    let int = isize::try_from(self).unwrap_or(if self > 0 {
+                        isize::MAX
+                    } else {
+                        isize::MIN
+                    });
+                    Value::Int(int).into()
+                }
+            }
+        )*
+    };
+}
+
+impl_int_conversions!(i32, i64, u32, usize);
+
+impl IntoLisp for &str {
+    fn into_lisp(self) -> Var {
+        Value::Str(self.to_string()).into()
+    }
+}
+
+//*** Containers ***//
+/// `nil` is `None`
+impl<T: FromLisp> FromLisp for Option<T> {
+    fn from_lisp(var: &Var) -> EResult<Self> {
+        match var.as_ref() {
+            Expr::Value(Value::Nil) => Ok(None),
+            _ => T::from_lisp(var).map(Some),
+        }
+    }
+}
+
+impl<T: IntoLisp> IntoLisp for Option<T> {
+    fn into_lisp(self) -> Var {
+        match self {
+            Some(val) => val.into_lisp(),
+            None => Value::Nil.into(),
+        }
+    }
+}
+
+/// Lists
+impl<T: FromLisp> FromLisp for Vec<T> {
+    fn from_lisp(var: &Var) -> EResult<Self> {
+        match var.as_ref() {
+            Expr::SExpr(items) => items.iter().map(T::from_lisp).collect(),
+            _ => Err(conversion_error("S-expression", var)),
+        }
+    }
+}
+
+impl<T: IntoLisp> IntoLisp for Vec<T> {
+    fn into_lisp(self) -> Var {
+        Expr::SExpr(
+            self.into_iter()
+                .map(IntoLisp::into_lisp)
+                .collect(),
+        )
+        .into()
+    }
+}
+
+/// Records, keyed by keyword name (without the `:`)
+impl<T: FromLisp> FromLisp for HashMap<String, T> {
+    fn from_lisp(var: &Var) -> EResult<Self> {
+        expect_record(var)?
+            .iter()
+            .map(|(key, val)| Ok((key.clone(), T::from_lisp(val)?)))
+            .collect()
+    }
+}
+
+impl<T: IntoLisp> IntoLisp for HashMap<String, T> {
+    fn into_lisp(self) -> Var {
+        Expr::Record(
+            self.into_iter()
+                .map(|(key, val)| (key, val.into_lisp()))
+                .collect(),
+        )
+        .into()
+    }
+}
+
+/// Tuples are lists of exactly that length
+macro_rules! impl_tuple_conversions {
+    ($(($len:literal; $($t:ident $i:tt),+)),* $(,)?) => {
+        $(
+            impl<$($t: FromLisp),+> FromLisp for ($($t,)+) {
+                fn from_lisp(var: &Var) -> EResult<Self> {
+                    match var.as_ref() {
+                        Expr::SExpr(items) if items.len() == $len => {
+                            Ok(($($t::from_lisp(&items[$i])?,)+))
+                        },
+                        _ => {
+                            Err(conversion_error(
+                                concat!("S-expression w/ length ", $len),
+                                var,
+                            ))
+                        },
+                    }
+                }
+            }
+
+            impl<$($t: IntoLisp),+> IntoLisp for ($($t,)+) {
+                fn into_lisp(self) -> Var {
+                    Expr::SExpr(vec![$(self.$i.into_lisp()),+]).into()
+                }
+            }
+        )*
+    };
+}
+
+impl_tuple_conversions!(
+    (1; A 0),
+    (2; A 0, B 1),
+    (3; A 0, B 1, C 2),
+    (4; A 0, B 1, C 2, D 3),
+);
+
+//*** Structs (used by the derive macros) ***//
+pub fn expect_record(var: &Var) -> EResult<&Mapping> {
+    match var.as_ref() {
+        Expr::Record(record) => Ok(record),
+        _ => Err(conversion_error("Record", var)),
+    }
+}
+
+/// Convert the value at `key` in a record. A missing key is read as `nil`, so
+/// it's fine for `Option` fields.
+pub fn record_field<T: FromLisp>(record: &Mapping, key: &str) -> EResult<T> {
+    match record.get(key) {
+        Some(val) => T::from_lisp(val),
+        None => {
+            T::from_lisp(&Value::Nil.into()).map_err(|_| {
+                EvalError::Type {
+                    expected: format!("Record with key :{key}"),
+                    actual: "Record without it".to_string(),
+                }
+            })
+        },
+    }
+}
+
+/******************\
+|* Host functions *|
+\******************/
+/// Rust functions whose arguments and return value convert to and from lisp
+/// values, see `Function::host`. Implemented for closures of up to 4
+/// arguments.
+pub trait HostFn<Args> {
+    fn num_args() -> usize;
+
+    /// Convert `args` (already arity checked), and call the function
+    fn call_with(&self, args: &SExpr) -> EResult<Var>;
+}
+
+macro_rules! impl_host_fn {
+    ($(($len:literal; $($t:ident $i:tt),*)),* $(,)?) => {
+        $(
+            impl<F, R, $($t),*> HostFn<($($t,)*)> for F
+            where
+                F: Fn($($t),*) -> R,
+                R: IntoLispResult,
+                $($t: FromLisp),*
+            {
+                fn num_args() -> usize {
+                    $len
+                }
+
+                #[allow(unused_variables)]
+                fn call_with(&self, args: &SExpr) -> EResult<Var> {
+                    self($($t::from_lisp(&args[$i])?),*).into_lisp_result()
+                }
+            }
+        )*
+    };
+}
+
+impl_host_fn!(
+    (0;),
+    (1; A 0),
+    (2; A 0, B 1),
+    (3; A 0, B 1, C 2),
+    (4; A 0, B 1, C 2, D 3),
+);
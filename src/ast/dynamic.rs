@@ -0,0 +1,48 @@
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+use super::Var;
+
+/// A late-bound (dynamically scoped) variable, defined with `defdynamic`.
+///
+/// Unlike everything else, closures capture a reference to the cell rather
+/// than its value, so `parameterize` can rebind it for the dynamic extent of
+/// a call. Looking up a symbol bound to a cell gives its current value.
+#[derive(Debug)]
+pub struct DynamicVar {
+    pub name: String,
+    value: RefCell<Var>,
+}
+
+impl DynamicVar {
+    pub fn new(name: &str, value: Var) -> Rc<Self> {
+        Rc::new(DynamicVar {
+            name: name.to_string(),
+            value: RefCell::new(value),
+        })
+    }
+
+    pub fn get(&self) -> Var {
+        self.value.borrow().clone()
+    }
+
+    /// Set a new value, returning the old one
+    pub fn replace(&self, value: Var) -> Var {
+        self.value.replace(value)
+    }
+}
+
+/// Cells are compared by identity: two variables with the same value are
+/// still different variables
+impl PartialEq for DynamicVar {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Display for DynamicVar {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#dynamic[{}]", self.name)
+    }
+}
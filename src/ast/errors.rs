@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use super::Arity;
+
 #[derive(Error, Debug)]
 pub enum ParseError {}
 
@@ -18,6 +20,9 @@ pub enum InternalError {
 
     #[error("Expression '{expression}' is not a literal.")]
     NotAValue { expression: String },
+
+    #[error("Could not write output: {0}")]
+    Output(#[from] std::io::Error),
 }
 
 #[derive(Error, Debug)]
@@ -38,13 +43,48 @@ pub enum EvalError {
     NotCallable(String),
 
     #[error(
-        "Function {name} takes {arity} arguments but got {num_args_provided}"
+        "Function {name} takes {} arguments, got {num_args_provided}",
+        .arity.describe()
     )]
     Arity {
         name: String,
-        arity: usize,
+        arity: Arity,
         num_args_provided: usize,
     },
+
+    #[error("Function {name} has no keyword argument ':{keyword}'")]
+    UnknownKeyword { name: String, keyword: String },
+
+    #[error("Function {name} is missing keyword argument ':{keyword}'")]
+    MissingKeyword { name: String, keyword: String },
+
+    #[error("Pattern {pattern} does not match {value}: {reason}")]
+    PatternMismatch {
+        pattern: String,
+        value: String,
+        reason: String,
+    },
+
+    #[error("Cannot shadow special form '{0}'")]
+    ShadowedSpecial(String),
+
+    #[error("Cannot bind special form '{special}' to '{name}'")]
+    AliasedSpecial { name: String, special: String },
+
+    #[error("Function {function} cannot return special form '{special}'")]
+    ReturnedSpecial { function: String, special: String },
+
+    #[error("Cannot pass special form '{special}' to function {function}")]
+    SpecialArgument { function: String, special: String },
+
+    #[error(
+        "Cannot set! captured variable '{0}': closures capture copies of \
+         values, use a box to share state"
+    )]
+    AssignedCapture(String),
+
+    #[error("Host type {type_name} has no method '{method}'")]
+    UnknownMethod { type_name: String, method: String },
 }
 
 pub type EResult<T> = Result<T, EvalError>;
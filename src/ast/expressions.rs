@@ -1,8 +1,12 @@
 use std::fmt::{Display, Formatter};
+use std::rc::Rc;
 
 use itertools::Itertools;
 
-use super::{EvalError, Function, Mapping, SpecialForm, Value};
+use super::{
+    DynamicVar, EvalError, Function, HostObject, Macro, Mapping, MutableBox,
+    SpecialForm, Value,
+};
 use crate::InternalError;
 use crate::ast::variables::Var;
 
@@ -23,6 +27,10 @@ pub enum Expr {
     Value(Value),
     Record(Mapping),
     Keyword(String),
+    Dynamic(Rc<DynamicVar>),
+    Box(Rc<MutableBox>),
+    Macro(Macro),
+    Host(HostObject),
 }
 
 impl Expr {
@@ -39,6 +47,20 @@ impl Expr {
             Expr::Function(_) => "Function",
             Expr::Special(_) => "SpecialForm",
             Expr::Record(_) => "Record",
+            Expr::Dynamic(_) => "Dynamic",
+            Expr::Box(_) => "Box",
+            Expr::Macro(_) => "Macro",
+            Expr::Host(_) => "Host",
+        }
+    }
+
+    /// The name of the special form this is (builtin, or a macro), if it is
+    /// one: these are subject to the rules in `Scope::set`
+    pub fn special_name(&self) -> Option<&str> {
+        match self {
+            Expr::Special(special) => Some(&special.name),
+            Expr::Macro(mac) => Some(&mac.name),
+            _ => None,
         }
     }
 
@@ -76,6 +98,30 @@ impl Expr {
         }
     }
 
+    pub fn expect_box(&self) -> Result<&Rc<MutableBox>, EvalError> {
+        match self {
+            Expr::Box(cell) => Ok(cell),
+            _other => {
+                Err(EvalError::Type {
+                    expected: "Box".to_string(),
+                    actual: self.type_str().to_string(),
+                })
+            },
+        }
+    }
+
+    pub fn expect_host(&self) -> Result<&HostObject, EvalError> {
+        match self {
+            Expr::Host(object) => Ok(object),
+            _other => {
+                Err(EvalError::Type {
+                    expected: "Host".to_string(),
+                    actual: self.type_str().to_string(),
+                })
+            },
+        }
+    }
+
     pub fn expect_sexp(&self) -> Result<&SExpr, EvalError> {
         match self {
             Expr::SExpr(sexp) => Ok(sexp),
@@ -125,6 +171,10 @@ impl Display for Expr {
             Expr::Function(func) => func.fmt(f),
             Expr::Special(s) => s.fmt(f),
             Expr::Record(rec) => write!(f, "{}", display_record(rec)),
+            Expr::Dynamic(cell) => cell.fmt(f),
+            Expr::Box(cell) => cell.fmt(f),
+            Expr::Macro(mac) => mac.fmt(f),
+            Expr::Host(object) => object.fmt(f),
         }
     }
 }
@@ -197,4 +247,5 @@ impl_raw_expr_conversions!(
     OwnedSExpr, SExpr;
     Function, Function;
     SpecialForm, Special;
+    HostObject, Host;
 );
@@ -0,0 +1,141 @@
+use std::any::{Any, TypeId};
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::Deref;
+use std::rc::Rc;
+
+use super::{
+    EResult, EvalError, Expr, FromLisp, IntoLisp, Var, conversion_error,
+};
+
+/// An opaque Rust value (a file handle, a parsed document...), passed through
+/// lisp code as is.
+///
+/// Lisp code can't look inside a host object: it can only pass it around,
+/// and call the methods the host registered for its type (see
+/// `Context::register_method`) with `(send object :method args...)`.
+#[derive(Clone)]
+pub struct HostObject {
+    type_name: String,
+    value: Rc<dyn Any>,
+}
+
+impl HostObject {
+    /// Named after the type of `value`, without its module path
+    pub fn new<T: Any>(value: T) -> Self {
+        Self::from_rc(Rc::new(value))
+    }
+
+    pub fn from_rc<T: Any>(value: Rc<T>) -> Self {
+        HostObject {
+            type_name: short_type_name::<T>().to_string(),
+            value,
+        }
+    }
+
+    /// With a custom type name, for printing and errors
+    pub fn named<T: Any>(type_name: &str, value: T) -> Self {
+        HostObject {
+            type_name: type_name.to_string(),
+            value: Rc::new(value),
+        }
+    }
+
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// The `TypeId` of the wrapped value (not of the `Rc`)
+    pub fn value_type_id(&self) -> TypeId {
+        self.value.as_ref().type_id()
+    }
+
+    /// The wrapped value, if it's a `T`
+    pub fn downcast<T: Any>(&self) -> EResult<Rc<T>> {
+        self.value
+            .clone()
+            .downcast::<T>()
+            .map_err(|_| {
+                EvalError::Type {
+                    expected: format!("#<{}>", short_type_name::<T>()),
+                    actual: self.to_string(),
+                }
+            })
+    }
+}
+
+/// Host objects are compared by identity, like boxes
+impl PartialEq for HostObject {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.value, &other.value)
+    }
+}
+
+impl Debug for HostObject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HostObject({})", self.type_name)
+    }
+}
+
+impl Display for HostObject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<{}>", self.type_name)
+    }
+}
+
+/// `std::any::type_name`, without module paths
+fn short_type_name<T: Any>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    // keep generic arguments (which have paths of their own) as they are
+    let path_end = name.find('<').unwrap_or(name.len());
+    match name[..path_end].rfind("::") {
+        Some(i) => &name[i + 2..],
+        None => name,
+    }
+}
+
+/// A host object known to hold a `T`: use it as an argument type of
+/// `Function::host`, to accept only `T`s
+pub struct Host<T>(pub Rc<T>);
+
+impl<T> Deref for Host<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Any> FromLisp for Host<T> {
+    fn from_lisp(var: &Var) -> EResult<Self> {
+        match var.as_ref() {
+            Expr::Host(object) => object.downcast().map(Host),
+            _ => {
+                Err(conversion_error(
+                    &format!("#<{}>", short_type_name::<T>()),
+                    var,
+                ))
+            },
+        }
+    }
+}
+
+impl<T: Any> IntoLisp for Host<T> {
+    fn into_lisp(self) -> Var {
+        Expr::Host(HostObject::from_rc(self.0)).into()
+    }
+}
+
+impl FromLisp for HostObject {
+    fn from_lisp(var: &Var) -> EResult<Self> {
+        match var.as_ref() {
+            Expr::Host(object) => Ok(object.clone()),
+            _ => Err(conversion_error("Host", var)),
+        }
+    }
+}
+
+impl IntoLisp for HostObject {
+    fn into_lisp(self) -> Var {
+        Expr::Host(self).into()
+    }
+}
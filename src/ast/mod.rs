@@ -1,13 +1,26 @@
+mod boxes;
 mod callables;
+mod conversions;
+mod dynamic;
 pub mod errors;
 mod expressions;
+mod host;
+mod patterns;
 mod records;
+mod syntax_rules;
 mod values;
 mod variables;
 
+pub use boxes::*;
 pub use callables::*;
+pub use conversions::*;
+pub use dynamic::*;
 pub use errors::*;
 pub use expressions::*;
+pub use host::*;
+pub use lisp_playground_derive::{FromLisp, IntoLisp};
+pub use patterns::*;
 pub use records::*;
+pub use syntax_rules::*;
 pub use values::*;
 pub use variables::*;
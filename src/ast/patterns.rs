@@ -0,0 +1,233 @@
+use std::fmt::{Display, Formatter};
+
+use itertools::Itertools;
+
+use super::{EResult, EvalError, Expr, Mapping, Var};
+use crate::Scope;
+
+/// A binding pattern, for destructuring values wherever names are bound
+/// (parameter lists, `defvar`/`define`, ...).
+///
+/// - `x` binds the whole value to `x`;
+/// - `(x (y z) & rest)` destructures an s-expression, item by item. Extra items
+///   are bound as a list to the pattern after `&`, if present;
+/// - `{:keys (name age) :pos (x y)}` destructures a record: `:keys` binds each
+///   listed name to the record's value for the same key, and any other `:key
+///   pattern` entry destructures the value for `key`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    Symbol(String),
+    List {
+        items: Vec<Pattern>,
+        rest: Option<Box<Pattern>>,
+    },
+    Record {
+        keys: Vec<String>,
+        entries: Vec<(String, Pattern)>,
+    },
+}
+
+impl Pattern {
+    pub fn from_expr(expr: &Expr) -> EResult<Pattern> {
+        match expr {
+            Expr::Symbol(name) => Ok(Pattern::Symbol(name.clone())),
+            Expr::SExpr(sexpr) => {
+                let (items, rest) = match sexpr.iter().position(
+                    |var| matches!(var.as_ref(), Expr::Symbol(s) if s == "&"),
+                ) {
+                    None => (&sexpr[..], None),
+                    Some(idx) => {
+                        let [rest] = &sexpr[idx + 1..] else {
+                            return Err(EvalError::Syntax {
+                                expected: "exactly one pattern after `&`"
+                                    .to_string(),
+                                actual: expr.to_string(),
+                            });
+                        };
+                        (
+                            &sexpr[..idx],
+                            Some(Box::new(Self::from_expr(rest)?)),
+                        )
+                    },
+                };
+                Ok(Pattern::List {
+                    items: items
+                        .iter()
+                        .map(|var| Self::from_expr(var))
+                        .collect::<EResult<_>>()?,
+                    rest,
+                })
+            },
+            Expr::Record(mapping) => {
+                let mut keys = Vec::new();
+                let mut entries = Vec::new();
+                // sorted, so errors and display are deterministic
+                for (key, var) in mapping
+                    .iter()
+                    .sorted_by_key(|(k, _)| *k)
+                {
+                    if key == "keys" {
+                        for name in var.expect_sexp()?.iter() {
+                            keys.push(name.expect_symbol()?.to_string());
+                        }
+                    } else {
+                        entries.push((key.clone(), Self::from_expr(var)?));
+                    }
+                }
+                Ok(Pattern::Record { keys, entries })
+            },
+            other => {
+                Err(EvalError::Syntax {
+                    expected: "Symbol, S-expression or Record pattern"
+                        .to_string(),
+                    actual: other.type_str().to_string(),
+                })
+            },
+        }
+    }
+
+    /// The pattern as it's written (the inverse of `from_expr`)
+    pub fn to_var(&self) -> Var {
+        match self {
+            Pattern::Symbol(name) => Expr::Symbol(name.clone()).into(),
+            Pattern::List { items, rest } => {
+                let mut sexpr: Vec<Var> = items
+                    .iter()
+                    .map(Pattern::to_var)
+                    .collect();
+                if let Some(rest) = rest {
+                    sexpr.push(Expr::Symbol("&".to_string()).into());
+                    sexpr.push(rest.to_var());
+                }
+                Expr::SExpr(sexpr).into()
+            },
+            Pattern::Record { keys, entries } => {
+                let mut record: Mapping = entries
+                    .iter()
+                    .map(|(key, pattern)| (key.clone(), pattern.to_var()))
+                    .collect();
+                if !keys.is_empty() {
+                    let keys = keys
+                        .iter()
+                        .map(|key| Expr::Symbol(key.clone()).into())
+                        .collect();
+                    record.insert("keys".to_string(), Expr::SExpr(keys).into());
+                }
+                Expr::Record(record).into()
+            },
+        }
+    }
+
+    /// All names bound by this pattern
+    pub fn names(&self) -> Vec<&str> {
+        match self {
+            Pattern::Symbol(name) => vec![name],
+            Pattern::List { items, rest } => {
+                items
+                    .iter()
+                    .chain(rest.as_deref())
+                    .flat_map(Pattern::names)
+                    .collect()
+            },
+            Pattern::Record { keys, entries } => {
+                keys.iter()
+                    .map(String::as_str)
+                    .chain(
+                        entries
+                            .iter()
+                            .flat_map(|(_, p)| p.names()),
+                    )
+                    .collect()
+            },
+        }
+    }
+
+    /// Destructure `value`, binding the names in `scope`
+    pub fn bind(&self, value: &Var, scope: &mut Scope) -> EResult<()> {
+        match self {
+            Pattern::Symbol(name) => scope.set(name, value.clone()),
+            Pattern::List { items, rest } => {
+                let Expr::SExpr(values) = value.as_ref() else {
+                    return Err(self.mismatch(value, "not an S-expression"));
+                };
+                let too_few = values.len() < items.len();
+                let too_many = rest.is_none() && values.len() > items.len();
+                if too_few || too_many {
+                    return Err(self.mismatch(
+                        value,
+                        &format!(
+                            "expected {} items, got {}",
+                            items.len(),
+                            values.len()
+                        ),
+                    ));
+                }
+
+                for (pattern, item) in items.iter().zip(values.iter()) {
+                    pattern.bind(item, scope)?;
+                }
+                if let Some(rest) = rest {
+                    let extra = values[items.len()..].to_vec();
+                    rest.bind(&Expr::SExpr(extra).into(), scope)?;
+                }
+                Ok(())
+            },
+            Pattern::Record { keys, entries } => {
+                let Expr::Record(mapping) = value.as_ref() else {
+                    return Err(self.mismatch(value, "not a Record"));
+                };
+                let get = |key: &str| {
+                    mapping.get(key).ok_or_else(|| {
+                        self.mismatch(value, &format!("missing key :{key}"))
+                    })
+                };
+
+                for key in keys.iter() {
+                    scope.set(key, get(key)?.clone())?;
+                }
+                for (key, pattern) in entries.iter() {
+                    pattern.bind(get(key)?, scope)?;
+                }
+                Ok(())
+            },
+        }
+    }
+
+    fn mismatch(&self, value: &Var, reason: &str) -> EvalError {
+        EvalError::PatternMismatch {
+            pattern: self.to_string(),
+            value: value.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Symbol(name) => write!(f, "{name}"),
+            Pattern::List { items, rest } => {
+                write!(f, "({}", items.iter().format(" "))?;
+                if let Some(rest) = rest {
+                    let sep = if items.is_empty() { "" } else { " " };
+                    write!(f, "{sep}& {rest}")?;
+                }
+                write!(f, ")")
+            },
+            Pattern::Record { keys, entries } => {
+                let keys = (!keys.is_empty())
+                    .then(|| format!(":keys ({})", keys.join(" ")));
+                let entries = entries
+                    .iter()
+                    .map(|(k, p)| format!(":{k} {p}"));
+                write!(
+                    f,
+                    "{{{}}}",
+                    keys.into_iter()
+                        .chain(entries)
+                        .format(" ")
+                )
+            },
+        }
+    }
+}
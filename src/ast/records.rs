@@ -35,6 +35,7 @@ pub struct TypeInfo {
     // implementations: HashMap<String>,
 }
 
+#[allow(dead_code)]
 pub struct Object {
     typeinfo: Rc<TypeInfo>,
     metadata: Mapping,
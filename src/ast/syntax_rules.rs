@@ -0,0 +1,618 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{EResult, EvalError, Expr, OwnedSExpr, SExpr, Var};
+use crate::{Context, Scope};
+
+const ELLIPSIS: &str = "...";
+const WILDCARD: &str = "_";
+
+/// Symbols that other forms look for by name (`cond`'s `else`, lambda list
+/// markers...), so they keep their names in expansions
+const AUXILIARY_SYNTAX: [&str; 6] =
+    ["else", "&", "&opt", "&key", "unquote", "unquote-splicing"];
+
+/// A hygienic, pattern-based macro, defined with
+///     `(define-syntax name (syntax-rules (literals...) (pattern template)...))`
+///
+/// A call expands the template of the first rule whose pattern matches it.
+/// In patterns, symbols are pattern variables (except for the literals,
+/// which match themselves, and `_`, which matches anything), and `p ...`
+/// matches any number of `p`s. In templates, pattern variables are replaced
+/// by what they matched, and `t ...` repeats `t` for each match of the
+/// pattern variables in it.
+///
+/// Symbols the template introduces itself are hygienic:
+/// 1) names the template binds (with `let`, `lambda`, `define`...) are renamed,
+///    afresh for each expansion, so they can't clash with the caller's names;
+/// 2) free names bound where the macro was defined are captured greedily when
+///    it's defined, like a closure's free variables, under aliases the caller
+///    can't shadow or redefine;
+/// 3) other free names (special forms, auxiliary syntax like `else` and `&`,
+///    names defined after the macro...) are kept as they are.
+///
+/// Quoted data (`quote`, and `quasiquote` outside of its `unquote`s) is kept
+/// as it's written, apart from pattern variables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxRules {
+    name: String,
+    literals: Vec<String>,
+    rules: Vec<Rule>,
+    /// Aliases for the templates' free names that were bound where the macro
+    /// was defined, bound to the same values there
+    aliases: HashMap<String, Var>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    /// Leaves out the macro keyword
+    pattern: OwnedSExpr,
+    template: Var,
+    /// The names the template binds, other than pattern variables
+    binders: HashSet<String>,
+}
+
+/// How a template's symbols are expanded
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Quoting {
+    /// Code: introduced names are hygienic
+    None,
+    /// Inside `quote`: data
+    Quote,
+    /// Inside `quasiquote`: data, except inside `unquote`s
+    Quasiquote,
+}
+
+/// What a pattern variable matched
+#[derive(Debug, Clone)]
+enum Matched {
+    One(Var),
+    /// One match per repetition of an ellipsis
+    Many(Vec<Matched>),
+}
+
+type Bindings = HashMap<String, Matched>;
+
+impl SyntaxRules {
+    /// Parse `(syntax-rules (literals...) (pattern template)...)` (the
+    /// arguments after `syntax-rules`), capturing the templates' free names
+    /// from `scope`, where their aliases are bound
+    pub fn new(
+        name: &str,
+        args: &SExpr,
+        scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<Self> {
+        let Some((literals, rules)) = args.split_first() else {
+            return Err(syntax_error(
+                "(syntax-rules (literals...) rules...)",
+                "()",
+            ));
+        };
+        let literals = literals
+            .expect_sexp()?
+            .iter()
+            .map(|literal| {
+                literal
+                    .expect_symbol()
+                    .map(str::to_string)
+            })
+            .collect::<EResult<Vec<_>>>()?;
+
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                match rule.as_ref() {
+                    Expr::SExpr(items) if items.len() == 2 => {
+                        let pattern = items[0].expect_sexp()?;
+                        if pattern.is_empty() {
+                            return Err(syntax_error(
+                                "(keyword patterns...)",
+                                "()",
+                            ));
+                        }
+                        check_pattern(&pattern[1..])?;
+                        let mut binders = HashSet::new();
+                        template_binders(&items[1], &mut binders);
+                        Ok(Rule {
+                            pattern: pattern[1..].to_vec(),
+                            template: items[1].clone(),
+                            binders,
+                        })
+                    },
+                    _ => {
+                        Err(syntax_error(
+                            "rule: (pattern template)",
+                            &rule.to_string(),
+                        ))
+                    },
+                }
+            })
+            .collect::<EResult<Vec<_>>>()?;
+
+        let mut syntax_rules = SyntaxRules {
+            name: name.to_string(),
+            literals,
+            rules,
+            aliases: HashMap::new(),
+        };
+        syntax_rules.capture_free_names(scope, ctx);
+        Ok(syntax_rules)
+    }
+
+    /// Expand a call with the (unevaluated) arguments `args`
+    pub fn expand(&self, args: &SExpr, ctx: &mut Context) -> EResult<Var> {
+        for rule in self.rules.iter() {
+            let mut bindings = Bindings::new();
+            if self.match_list(&rule.pattern, args, &mut bindings) {
+                let mut renames = Renames {
+                    id: ctx.next_expansion_id(),
+                    binders: &rule.binders,
+                    names: HashMap::new(),
+                };
+                return self.expand_template(
+                    &rule.template,
+                    &bindings,
+                    &mut renames,
+                    Quoting::None,
+                );
+            }
+        }
+        Err(syntax_error(
+            &format!(
+                "a form matching one of {}'s patterns",
+                self.name
+            ),
+            &Expr::SExpr(args.to_vec()).to_string(),
+        ))
+    }
+
+    /// Alias the templates' free names that are bound in `scope` (other than
+    /// special forms, which can't be rebound anyway), binding the aliases to
+    /// the same values in `scope`
+    fn capture_free_names(&mut self, scope: &mut Scope, ctx: &mut Context) {
+        let id = ctx.next_expansion_id();
+        for rule in self.rules.iter() {
+            let pattern_vars = self.pattern_vars(&rule.pattern);
+            for name in code_symbols(&rule.template, Quoting::None) {
+                if pattern_vars.contains(name)
+                    || rule.binders.contains(name)
+                    || self.aliases.contains_key(name)
+                    || self.keeps_name(name)
+                {
+                    continue;
+                }
+                let Some(value) = scope.lookup(name) else {
+                    continue;
+                };
+                if value.special_name().is_some() {
+                    continue;
+                }
+                let alias = format!("{name}%{id}");
+                scope.set_unchecked(&alias, value);
+                self.aliases
+                    .insert(name.to_string(), Expr::Symbol(alias).into());
+            }
+        }
+    }
+
+    /// Auxiliary syntax, and the macro's own name (for recursive macros)
+    fn keeps_name(&self, name: &str) -> bool {
+        AUXILIARY_SYNTAX.contains(&name) || name == self.name
+    }
+
+    /***********\
+    |* Matching *|
+    \***********/
+    fn pattern_vars(&self, pattern: &SExpr) -> HashSet<String> {
+        pattern
+            .iter()
+            .flat_map(symbols)
+            .filter(|name| {
+                *name != ELLIPSIS
+                    && *name != WILDCARD
+                    && !self
+                        .literals
+                        .iter()
+                        .any(|literal| literal == name)
+            })
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn match_pattern(
+        &self,
+        pattern: &Var,
+        form: &Var,
+        bindings: &mut Bindings,
+    ) -> bool {
+        match pattern.as_ref() {
+            Expr::Symbol(name) if name == WILDCARD => true,
+            Expr::Symbol(name) if self.literals.contains(name) => {
+                matches!(form.as_ref(), Expr::Symbol(s) if s == name)
+            },
+            Expr::Symbol(name) => {
+                bindings.insert(name.clone(), Matched::One(form.clone()));
+                true
+            },
+            Expr::SExpr(items) => {
+                match form.as_ref() {
+                    Expr::SExpr(forms) => self.match_list(items, forms, bindings),
+                    _ => false,
+                }
+            },
+            _ => pattern == form,
+        }
+    }
+
+    fn match_list(
+        &self,
+        pattern: &SExpr,
+        forms: &SExpr,
+        bindings: &mut Bindings,
+    ) -> bool {
+        let Some(pos) = pattern.iter().position(is_ellipsis) else {
+            return pattern.len() == forms.len()
+                && pattern
+                    .iter()
+                    .zip(forms.iter())
+                    .all(|(p, f)| self.match_pattern(p, f, bindings));
+        };
+
+        // `before... repeated ... after...`
+        let before = &pattern[..pos - 1];
+        let repeated = &pattern[pos - 1];
+        let after = &pattern[pos + 1..];
+        if forms.len() < before.len() + after.len() {
+            return false;
+        }
+        let (head, rest) = forms.split_at(before.len());
+        let (middle, tail) = rest.split_at(rest.len() - after.len());
+        if !self.match_list(before, head, bindings)
+            || !self.match_list(after, tail, bindings)
+        {
+            return false;
+        }
+
+        let mut matches = Vec::new();
+        for form in middle.iter() {
+            let mut item_bindings = Bindings::new();
+            if !self.match_pattern(repeated, form, &mut item_bindings) {
+                return false;
+            }
+            matches.push(item_bindings);
+        }
+        for name in self.pattern_vars(std::slice::from_ref(repeated)) {
+            let items = matches
+                .iter_mut()
+                .filter_map(|item_bindings| item_bindings.remove(&name))
+                .collect();
+            bindings.insert(name, Matched::Many(items));
+        }
+        true
+    }
+
+    /*************\
+    |* Expansion *|
+    \*************/
+    fn expand_template(
+        &self,
+        template: &Var,
+        bindings: &Bindings,
+        renames: &mut Renames,
+        quoting: Quoting,
+    ) -> EResult<Var> {
+        match template.as_ref() {
+            Expr::Symbol(name) => {
+                match bindings.get(name) {
+                    Some(Matched::One(form)) => Ok(form.clone()),
+                    Some(Matched::Many(_)) => {
+                        Err(syntax_error(
+                            &format!(
+                                "'{ELLIPSIS}' after pattern variable '{name}'"
+                            ),
+                            name,
+                        ))
+                    },
+                    None if quoting == Quoting::None => {
+                        Ok(self.introduce(name, renames))
+                    },
+                    None => Ok(template.clone()),
+                }
+            },
+            Expr::SExpr(items) => {
+                let inner = match head_symbol(items) {
+                    _ if items.is_empty() => quoting,
+                    Some(head) if bindings.contains_key(head) => quoting,
+                    Some("quote") if quoting == Quoting::None => Quoting::Quote,
+                    Some("quasiquote") if quoting == Quoting::None => {
+                        Quoting::Quasiquote
+                    },
+                    Some("unquote" | "unquote-splicing")
+                        if quoting == Quoting::Quasiquote =>
+                    {
+                        Quoting::None
+                    },
+                    _ => quoting,
+                };
+                let mut expanded = OwnedSExpr::new();
+                let mut iter = items.iter().peekable();
+                let mut is_head = true;
+                while let Some(item) = iter.next() {
+                    // the head of a quoting form keeps the outer quoting
+                    let quoting = if is_head { quoting } else { inner };
+                    is_head = false;
+                    if iter
+                        .peek()
+                        .is_some_and(|next| is_ellipsis(next))
+                    {
+                        iter.next();
+                        expanded.extend(
+                            self.expand_repeated(
+                                item, bindings, renames, quoting,
+                            )?,
+                        );
+                    } else {
+                        expanded.push(
+                            self.expand_template(
+                                item, bindings, renames, quoting,
+                            )?,
+                        );
+                    }
+                }
+                Ok(Expr::SExpr(expanded).into())
+            },
+            _ => Ok(template.clone()),
+        }
+    }
+
+    /// Expand `template ...`: once for each match of the repeated pattern
+    /// variables in `template`
+    fn expand_repeated(
+        &self,
+        template: &Var,
+        bindings: &Bindings,
+        renames: &mut Renames,
+        quoting: Quoting,
+    ) -> EResult<OwnedSExpr> {
+        let repeated: Vec<(&str, &Vec<Matched>)> = symbols(template)
+            .into_iter()
+            .filter_map(|name| {
+                match bindings.get(name) {
+                    Some(Matched::Many(items)) => Some((name, items)),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        let Some((_, first)) = repeated.first() else {
+            return Err(syntax_error(
+                &format!("a pattern variable matched with '{ELLIPSIS}'"),
+                &template.to_string(),
+            ));
+        };
+        if repeated
+            .iter()
+            .any(|(_, items)| items.len() != first.len())
+        {
+            return Err(syntax_error(
+                "pattern variables repeated the same number of times",
+                &template.to_string(),
+            ));
+        }
+
+        (0..first.len())
+            .map(|i| {
+                let mut item_bindings = bindings.clone();
+                for (name, items) in repeated.iter() {
+                    item_bindings.insert(name.to_string(), items[i].clone());
+                }
+                self.expand_template(template, &item_bindings, renames, quoting)
+            })
+            .collect()
+    }
+
+    /// A name introduced by a template, outside of quoted data (see the
+    /// rules above)
+    fn introduce(&self, name: &str, renames: &mut Renames) -> Var {
+        if self.keeps_name(name) {
+            Expr::Symbol(name.to_string()).into()
+        } else if renames.binders.contains(name) {
+            renames.rename(name)
+        } else if let Some(alias) = self.aliases.get(name) {
+            alias.clone()
+        } else {
+            Expr::Symbol(name.to_string()).into()
+        }
+    }
+}
+
+/// Fresh names for the names an expansion binds. `%` isn't allowed in
+/// symbols in source code, so these can't clash with the caller's names.
+struct Renames<'a> {
+    id: usize,
+    binders: &'a HashSet<String>,
+    names: HashMap<String, Var>,
+}
+
+impl Renames<'_> {
+    fn rename(&mut self, name: &str) -> Var {
+        let id = self.id;
+        self.names
+            .entry(name.to_string())
+            .or_insert_with(|| Expr::Symbol(format!("{name}%{id}")).into())
+            .clone()
+    }
+}
+
+/// Add the names `template` binds, as a binding form or anywhere inside it,
+/// to `binders`. Quoted data binds nothing.
+fn template_binders(template: &Var, binders: &mut HashSet<String>) {
+    let Expr::SExpr(items) = template.as_ref() else {
+        return;
+    };
+    let rest = items.get(1..).unwrap_or_default();
+    match head_symbol(items) {
+        Some("quote" | "quasiquote") => return,
+        Some("lambda") => {
+            if let Some(params) = rest.first() {
+                lambda_list_binders(params, binders);
+            }
+        },
+        Some("case-lambda") => {
+            for clause in rest.iter() {
+                if let Expr::SExpr(clause) = clause.as_ref() {
+                    if let Some(params) = clause.first() {
+                        lambda_list_binders(params, binders);
+                    }
+                }
+            }
+        },
+        Some("let" | "let*" | "letrec") => {
+            if let Some(Expr::SExpr(bindings)) =
+                rest.first().map(|var| var.as_ref())
+            {
+                for binding in bindings.iter() {
+                    if let Expr::SExpr(binding) = binding.as_ref() {
+                        if let Some(pattern) = binding.first() {
+                            binders.extend(pattern_symbols(pattern));
+                        }
+                    }
+                }
+            }
+        },
+        Some("define" | "defvar" | "defdynamic") => {
+            match rest.first().map(|var| var.as_ref()) {
+                // `(define (name params...) body...)`
+                Some(Expr::SExpr(signature)) if !signature.is_empty() => {
+                    binders.extend(pattern_symbols(&signature[0]));
+                    lambda_list_binders(
+                        &Expr::SExpr(signature[1..].to_vec()).into(),
+                        binders,
+                    );
+                },
+                Some(_) => binders.extend(pattern_symbols(&rest[0])),
+                None => {},
+            }
+        },
+        Some("declare") => binders.extend(rest.iter().flat_map(pattern_symbols)),
+        _ => {},
+    }
+    for item in items.iter() {
+        template_binders(item, binders);
+    }
+}
+
+/// The names a lambda list binds: not the markers, nor the labels and
+/// defaults of optional and keyword parameters
+fn lambda_list_binders(params: &Var, binders: &mut HashSet<String>) {
+    let Expr::SExpr(params) = params.as_ref() else {
+        return;
+    };
+    let mut with_defaults = false;
+    for param in params.iter() {
+        match param.as_ref() {
+            Expr::Symbol(marker) if marker == "&opt" || marker == "&key" => {
+                with_defaults = true;
+            },
+            // `(name default)` or `(:label name default)`
+            Expr::SExpr(param) if with_defaults => {
+                if let Some(name) = param
+                    .iter()
+                    .find_map(|var| var.expect_symbol().ok())
+                {
+                    binders.insert(name.to_string());
+                }
+            },
+            _ => binders.extend(pattern_symbols(param)),
+        }
+    }
+}
+
+/// The names a destructuring pattern binds
+fn pattern_symbols(pattern: &Var) -> impl Iterator<Item = String> + '_ {
+    symbols(pattern)
+        .into_iter()
+        .filter(|name| !AUXILIARY_SYNTAX.contains(name))
+        .map(str::to_string)
+}
+
+/// The symbols in `var` that aren't quoted data, in order
+fn code_symbols(var: &Var, quoting: Quoting) -> Vec<&str> {
+    match var.as_ref() {
+        Expr::Symbol(name) if quoting == Quoting::None => vec![name.as_str()],
+        Expr::SExpr(items) => {
+            let inner = match head_symbol(items) {
+                Some("quote") if quoting == Quoting::None => Quoting::Quote,
+                Some("quasiquote") if quoting == Quoting::None => {
+                    Quoting::Quasiquote
+                },
+                Some("unquote" | "unquote-splicing")
+                    if quoting == Quoting::Quasiquote =>
+                {
+                    Quoting::None
+                },
+                _ => quoting,
+            };
+            // the head of a quoting form keeps the outer quoting
+            items
+                .iter()
+                .enumerate()
+                .flat_map(|(i, item)| {
+                    code_symbols(item, if i == 0 { quoting } else { inner })
+                })
+                .collect()
+        },
+        _ => Vec::new(),
+    }
+}
+
+fn head_symbol(items: &SExpr) -> Option<&str> {
+    items
+        .first()
+        .and_then(|head| head.expect_symbol().ok())
+}
+
+/// Check that each list in a pattern has at most one ellipsis, after an item
+fn check_pattern(items: &SExpr) -> EResult<()> {
+    let ellipses: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| is_ellipsis(item))
+        .map(|(i, _)| i)
+        .collect();
+    if ellipses.len() > 1 || ellipses.first() == Some(&0) {
+        return Err(syntax_error(
+            &format!("at most one '{ELLIPSIS}' per list, after a pattern"),
+            &Expr::SExpr(items.to_vec()).to_string(),
+        ));
+    }
+    items
+        .iter()
+        .filter_map(|item| {
+            match item.as_ref() {
+                Expr::SExpr(inner) => Some(inner),
+                _ => None,
+            }
+        })
+        .try_for_each(|inner| check_pattern(inner))
+}
+
+fn is_ellipsis(var: &Var) -> bool {
+    matches!(var.as_ref(), Expr::Symbol(s) if s == ELLIPSIS)
+}
+
+/// All the symbols in `var`, in order
+fn symbols(var: &Var) -> Vec<&str> {
+    match var.as_ref() {
+        Expr::Symbol(name) => vec![name.as_str()],
+        Expr::SExpr(items) => items.iter().flat_map(symbols).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn syntax_error(expected: &str, actual: &str) -> EvalError {
+    EvalError::Syntax {
+        expected: expected.to_string(),
+        actual: actual.to_string(),
+    }
+}
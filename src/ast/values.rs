@@ -24,6 +24,18 @@ impl Value {
     pub fn expr(self) -> Expr {
         Expr::Value(self)
     }
+
+    pub fn type_str(&self) -> &'static str {
+        match self {
+            Value::Str(_) => "Str",
+            Value::Char(_) => "Char",
+            Value::Int(_) => "Int",
+            Value::Bytes(_) => "Bytes",
+            Value::Float(_) => "Float",
+            Value::Bool(_) => "Bool",
+            Value::Nil => "Nil",
+        }
+    }
 }
 
 impl Display for Value {
@@ -54,6 +66,8 @@ impl Display for Value {
 // converted into any specific rust type. Thus, we end up with
 // `TryFrom<Ctype> for rust_type`, which at least makes it easy to _try_ to do the
 // conversion and handle errors if not possible.
+//
+// See `FromLisp`/`IntoLisp` for conversions of whole lisp values.
 macro_rules! impl_value_conversions {
     ($($t:ty, $v:ident);* $(;)?) => {
         $(
@@ -65,8 +79,9 @@ macro_rules! impl_value_conversions {
                         Ok(native_val.clone())
                     } else {
                         Err(InternalError::Conversion{
-                            builtin_type: "CType::$v".to_string(),
-                            rust_type:"$t".to_string()
+                            builtin_type: concat!("Value::", stringify!($v))
+                                .to_string(),
+                            rust_type: stringify!($t).to_string(),
                         })
                     }
                 }
@@ -5,21 +5,56 @@ use std::rc::Rc;
 use crate::ast::Expr;
 
 /// `Vars` are our AST nodes, represented as a pointer to an
-/// expression
+/// expression, and the source line it was parsed from (if any)
 
-#[derive(Debug, PartialEq, Clone)]
-pub struct Var(Rc<Expr>);
+#[derive(Clone)]
+pub struct Var {
+    expr: Rc<Expr>,
+    line: Option<usize>,
+}
 
 impl Var {
     pub fn new(expr: Expr) -> Self {
-        Var(Rc::new(expr))
+        Var {
+            expr: Rc::new(expr),
+            line: None,
+        }
+    }
+
+    /// A node parsed from the (1-based) `line` of its source
+    pub fn at_line(expr: Expr, line: usize) -> Self {
+        Var {
+            expr: Rc::new(expr),
+            line: Some(line),
+        }
+    }
+
+    /// The line this node was parsed from. Nodes built during evaluation
+    /// (and by `parse_text`) have none.
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+}
+
+// the line is only bookkeeping: nodes are equal if their expressions are
+impl PartialEq for Var {
+    fn eq(&self, other: &Self) -> bool {
+        self.expr == other.expr
+    }
+}
+
+impl std::fmt::Debug for Var {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Var")
+            .field(&self.expr)
+            .finish()
     }
 }
 
 impl Display for Var {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         // just delegate to the actual expr for now
-        self.0.fmt(f)
+        self.expr.fmt(f)
     }
 }
 
@@ -27,13 +62,13 @@ impl Deref for Var {
     type Target = Rc<Expr>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.expr
     }
 }
 
 impl DerefMut for Var {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.expr
     }
 }
 
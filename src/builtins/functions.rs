@@ -1,9 +1,9 @@
 use crate::ast::Expr::Record;
 use crate::ast::{
-    Arity, CallForm, Expr, Function, InternalError, Mapping, OwnedSExpr, SExpr,
-    Value, Var,
+    Arity, CallForm, Expr, Function, InternalError, Mapping, Metadata,
+    MutableBox, OwnedSExpr, Parameters, SExpr, Value, Var,
 };
-use crate::{EResult, EvalError, Scope};
+use crate::{Context, EResult, EvalError, Scope};
 
 pub(super) trait BuiltinFnBuilder {
     fn register(scope: &mut Scope) {
@@ -11,22 +11,29 @@ pub(super) trait BuiltinFnBuilder {
         let form: Var = Expr::Function(Function {
             name: names.first().unwrap().to_string(),
             arity: Self::arity(),
-            arguments: Self::arguments()
-                .into_iter()
-                .map(|s| s.to_string())
-                .collect(),
+            params: Parameters::positional(
+                Self::arguments()
+                    .into_iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            ),
             form: CallForm::Builtin(Self::eval),
+            meta: Metadata::with_doc(Self::doc()),
         })
         .into();
 
         names
             .into_iter()
-            .for_each(|s| scope.set(s, form.clone()))
+            .for_each(|s| scope.set_unchecked(s, form.clone()))
     }
 
     /// names to bind to this function
     fn names() -> Vec<&'static str>;
 
+    /// what the function does, for `doc`: the user-facing documentation, so
+    /// the builder's own doc comment only holds implementation notes
+    fn doc() -> &'static str;
+
     /// names of the function's arguments
     fn arguments() -> Vec<&'static str>;
 
@@ -34,8 +41,9 @@ pub(super) trait BuiltinFnBuilder {
     fn arity() -> Arity;
 
     /// Callback to evaluate a call to the function.
-    /// Will be be passed an s-exp of its arguments' values.
-    fn eval(sexpr: &SExpr) -> EResult<Var>;
+    /// Will be be passed an s-exp of its arguments' values, and the
+    /// interpreter context (for output, or calling other functions).
+    fn eval(sexpr: &SExpr, ctx: &mut Context) -> EResult<Var>;
 }
 
 /************\
@@ -51,6 +59,10 @@ impl BuiltinFnBuilder for IdentityFnBuilder {
         vec!["I", "echo"]
     }
 
+    fn doc() -> &'static str {
+        "`(echo x)`: returns its (evaluated) argument unchanged."
+    }
+
     fn arguments() -> Vec<&'static str> {
         vec!["s"]
     }
@@ -59,7 +71,7 @@ impl BuiltinFnBuilder for IdentityFnBuilder {
         Arity::Fixed(1)
     }
 
-    fn eval(args: &SExpr) -> EResult<Var> {
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
         let val = args.first().unwrap();
         Ok(val.clone())
     }
@@ -74,6 +86,10 @@ impl BuiltinFnBuilder for PrintFnBuilder {
         vec!["print"]
     }
 
+    fn doc() -> &'static str {
+        "`(print s)`: writes the string `s` and a newline to the output."
+    }
+
     fn arguments() -> Vec<&'static str> {
         vec!["s"]
     }
@@ -82,11 +98,11 @@ impl BuiltinFnBuilder for PrintFnBuilder {
         Arity::Fixed(1) // TODO: make it variadic
     }
 
-    fn eval(args: &SExpr) -> EResult<Var> {
+    fn eval(args: &SExpr, ctx: &mut Context) -> EResult<Var> {
         let expression = args.first().unwrap().as_ref();
         let val: &Value = expression.try_into()?;
         let s: &str = val.try_into()?;
-        println!("{s}");
+        writeln!(ctx.output(), "{s}").map_err(InternalError::from)?;
         Ok(Var::new(Expr::empty()))
     }
 }
@@ -100,6 +116,10 @@ impl BuiltinFnBuilder for LenFnBuilder {
         vec!["len"]
     }
 
+    fn doc() -> &'static str {
+        "`(len list)`: the number of items in `list`."
+    }
+
     fn arguments() -> Vec<&'static str> {
         vec!["s-exp"]
     }
@@ -108,7 +128,7 @@ impl BuiltinFnBuilder for LenFnBuilder {
         Arity::Fixed(1)
     }
 
-    fn eval(args: &SExpr) -> EResult<Var> {
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
         let arg = args.first().unwrap().expect_sexp()?;
 
         // PANIC: Theoretically could panic if length is too big for isize
@@ -125,6 +145,10 @@ impl BuiltinFnBuilder for FirstFnBuilder {
         vec!["first", "car"]
     }
 
+    fn doc() -> &'static str {
+        "`(first list)`: the first item of `list`."
+    }
+
     fn arguments() -> Vec<&'static str> {
         vec!["s-exp"]
     }
@@ -133,7 +157,7 @@ impl BuiltinFnBuilder for FirstFnBuilder {
         Arity::Fixed(1)
     }
 
-    fn eval(args: &SExpr) -> EResult<Var> {
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
         let arg = args.first().unwrap().expect_sexp()?;
 
         if let Some(first_el) = arg.first() {
@@ -153,6 +177,10 @@ impl BuiltinFnBuilder for ConcatFnBuilder {
         vec!["concat"]
     }
 
+    fn doc() -> &'static str {
+        "`(concat list1 list2)`: the items of `list1`, then those of `list2`."
+    }
+
     fn arguments() -> Vec<&'static str> {
         vec!["s-exp1", "s-exp2"]
     }
@@ -161,8 +189,8 @@ impl BuiltinFnBuilder for ConcatFnBuilder {
         Arity::Fixed(2)
     }
 
-    fn eval(args: &SExpr) -> EResult<Var> {
-        let first = args.get(0).unwrap().expect_sexp()?;
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
+        let first = args.first().unwrap().expect_sexp()?;
         let second = args.get(1).unwrap().expect_sexp()?;
 
         Ok(Expr::SExpr(
@@ -176,6 +204,29 @@ impl BuiltinFnBuilder for ConcatFnBuilder {
     }
 }
 
+pub(super) struct ListFnBuilder {}
+impl BuiltinFnBuilder for ListFnBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["list"]
+    }
+
+    fn doc() -> &'static str {
+        "`(list items...)`: a list of the (evaluated) arguments."
+    }
+
+    fn arguments() -> Vec<&'static str> {
+        vec!["items"]
+    }
+
+    fn arity() -> Arity {
+        Arity::Variadic
+    }
+
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
+        Ok(Expr::SExpr(args.to_vec()).into())
+    }
+}
+
 /*********\
 |* Rest *|
 \*********/
@@ -185,6 +236,10 @@ impl BuiltinFnBuilder for RestFnBuilder {
         vec!["rest", "cdr"]
     }
 
+    fn doc() -> &'static str {
+        "`(rest list)`: all the items of `list` after the first."
+    }
+
     fn arguments() -> Vec<&'static str> {
         vec!["s-exp"]
     }
@@ -193,7 +248,7 @@ impl BuiltinFnBuilder for RestFnBuilder {
         Arity::Fixed(1)
     }
 
-    fn eval(args: &SExpr) -> EResult<Var> {
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
         Ok(Expr::SExpr(
             args.first().unwrap().expect_sexp()?[1..]
                 .iter()
@@ -224,6 +279,10 @@ impl BuiltinFnBuilder for RecordFnBuilder {
         vec!["record"]
     }
 
+    fn doc() -> &'static str {
+        "`(record (list :key value)...)`: a record with the given entries."
+    }
+
     fn arguments() -> Vec<&'static str> {
         vec!["kv_pairs"]
     }
@@ -232,13 +291,13 @@ impl BuiltinFnBuilder for RecordFnBuilder {
         Arity::Variadic
     }
 
-    fn eval(args: &SExpr) -> EResult<Var> {
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
         args.iter()
             .map(|v| {
                 v.expect_sexp_with_len(2)
                     .and_then(|vec| {
                         Ok((
-                            vec.get(0)
+                            vec.first()
                                 .unwrap()
                                 .expect_keyword()?
                                 .to_owned(),
@@ -251,6 +310,152 @@ impl BuiltinFnBuilder for RecordFnBuilder {
     }
 }
 
+/************\
+|* Captures *|
+\************/
+pub(super) struct CapturesFnBuilder {}
+impl BuiltinFnBuilder for CapturesFnBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["captures"]
+    }
+
+    fn doc() -> &'static str {
+        "`(captures f)`: a record of the values `f` captured when it was created \
+         (empty for builtins)."
+    }
+
+    fn arguments() -> Vec<&'static str> {
+        vec!["f"]
+    }
+
+    fn arity() -> Arity {
+        Arity::Fixed(1)
+    }
+
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
+        let func = args.first().unwrap().expect_fn()?;
+        Ok(Record(func.captures().unwrap_or_default()).into())
+    }
+}
+
+/*********\
+|* Boxes *|
+\*********/
+pub(super) struct BoxFnBuilder {}
+impl BuiltinFnBuilder for BoxFnBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["box"]
+    }
+
+    fn doc() -> &'static str {
+        "`(box value)`: a new mutable box holding `value`."
+    }
+
+    fn arguments() -> Vec<&'static str> {
+        vec!["value"]
+    }
+
+    fn arity() -> Arity {
+        Arity::Fixed(1)
+    }
+
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
+        Ok(Expr::Box(MutableBox::new(
+            args.first().unwrap().clone(),
+        ))
+        .into())
+    }
+}
+
+pub(super) struct UnboxFnBuilder {}
+impl BuiltinFnBuilder for UnboxFnBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["unbox"]
+    }
+
+    fn doc() -> &'static str {
+        "`(unbox b)`: the current contents of box `b`."
+    }
+
+    fn arguments() -> Vec<&'static str> {
+        vec!["box"]
+    }
+
+    fn arity() -> Arity {
+        Arity::Fixed(1)
+    }
+
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
+        Ok(args
+            .first()
+            .unwrap()
+            .expect_box()?
+            .get())
+    }
+}
+
+pub(super) struct SetBoxFnBuilder {}
+impl BuiltinFnBuilder for SetBoxFnBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["set-box!"]
+    }
+
+    fn doc() -> &'static str {
+        "`(set-box! b value)`: replaces the contents of box `b`, returning \
+         `value`."
+    }
+
+    fn arguments() -> Vec<&'static str> {
+        vec!["box", "value"]
+    }
+
+    fn arity() -> Arity {
+        Arity::Fixed(2)
+    }
+
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
+        let cell = args.first().unwrap().expect_box()?;
+        let value = args.get(1).unwrap().clone();
+        cell.replace(value.clone());
+        Ok(value)
+    }
+}
+
+pub(super) struct SwapFnBuilder {}
+impl BuiltinFnBuilder for SwapFnBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["swap!"]
+    }
+
+    fn doc() -> &'static str {
+        "`(swap! b f args...)`: replaces the contents of box `b` with `(f \
+         contents args...)`, returning the new contents."
+    }
+
+    fn arguments() -> Vec<&'static str> {
+        vec!["box", "f", "args"]
+    }
+
+    fn arity() -> Arity {
+        Arity::AtLeast(2)
+    }
+
+    fn eval(args: &SExpr, ctx: &mut Context) -> EResult<Var> {
+        use crate::eval::eval_function;
+
+        let cell = args.first().unwrap().expect_box()?;
+        let func = args.get(1).unwrap().expect_fn()?;
+
+        // the box isn't borrowed while `f` runs, so `f` may use it too
+        let mut call_args = vec![cell.get()];
+        call_args.extend_from_slice(&args[2..]);
+        let value = eval_function(func, call_args, ctx)?;
+
+        cell.replace(value.clone());
+        Ok(value)
+    }
+}
+
 /*********\
 |* Add   *|
 \*********/
@@ -263,6 +468,11 @@ impl BuiltinFnBuilder for AddFnBuilder {
         vec!["add", "+"]
     }
 
+    fn doc() -> &'static str {
+        "`(+ x y)`: the sum of two numbers (a float if either is one), or the \
+         concatenation of two strings or chars as a string."
+    }
+
     fn arguments() -> Vec<&'static str> {
         vec!["x", "y"]
     }
@@ -273,14 +483,14 @@ impl BuiltinFnBuilder for AddFnBuilder {
 
     /// temporary add implementation
     /// This needs a type system to do dispatch for us.
-    fn eval(args: &SExpr) -> EResult<Var> {
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
         let ctypes: Vec<&Value> = args
             .iter()
             .map(|expr| expr.as_ref().try_into())
             .collect::<Result<Vec<&Value>, InternalError>>()?;
 
         assert_eq!(ctypes.len(), 2);
-        let ct1 = ctypes.get(0).unwrap();
+        let ct1 = ctypes.first().unwrap();
         let ct2 = ctypes.get(1).unwrap();
 
         // awful, just awful. We need types.
@@ -336,6 +546,11 @@ impl BuiltinFnBuilder for RangeFnBuilder {
         vec!["range"]
     }
 
+    fn doc() -> &'static str {
+        "`(range start end)`: the integers from `start` up to (not including) \
+         `end`."
+    }
+
     fn arguments() -> Vec<&'static str> {
         vec!["start", "end"]
     }
@@ -344,13 +559,13 @@ impl BuiltinFnBuilder for RangeFnBuilder {
         Arity::Fixed(2)
     }
 
-    fn eval(args: &SExpr) -> EResult<Var> {
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
         let ctypes: Vec<isize> = args
             .iter()
             .map(_var_to_int)
             .collect::<EResult<Vec<isize>>>()?;
 
-        let start = *ctypes.get(0).unwrap();
+        let start = *ctypes.first().unwrap();
         let end = *ctypes.get(1).unwrap();
         Ok(Expr::SExpr(
             (start..end)
@@ -385,7 +600,11 @@ fn _var_to_int(var: &Var) -> EResult<isize> {
 pub(super) struct MapFnBuilder {}
 impl BuiltinFnBuilder for MapFnBuilder {
     fn names() -> Vec<&'static str> {
-        vec!["add", "+"]
+        vec!["map"]
+    }
+
+    fn doc() -> &'static str {
+        "`(map f list)`: the list of `(f item)` for each item of `list`."
     }
 
     fn arguments() -> Vec<&'static str> {
@@ -396,19 +615,257 @@ impl BuiltinFnBuilder for MapFnBuilder {
         Arity::Fixed(2)
     }
 
-    fn eval(args: &SExpr) -> EResult<Var> {
+    fn eval(args: &SExpr, ctx: &mut Context) -> EResult<Var> {
         use crate::eval::eval_function;
 
-        let mapfn = args.get(0).unwrap().expect_fn()?;
+        let mapfn = args.first().unwrap().expect_fn()?;
         let vals = args.get(1).unwrap().expect_sexp()?;
 
-        vals.into_iter()
-            .map(|v| eval_function(mapfn, vec![v.clone()]))
+        vals.iter()
+            .map(|v| eval_function(mapfn, vec![v.clone()], ctx))
             .collect::<EResult<Vec<Var>>>()
             .map(|v| Expr::SExpr(v).into())
     }
 }
 
+/*****************\
+|* Eval and apply *|
+\*****************/
+/// The fresh scope is a child of the context's root scope. Like any other
+/// function, `eval` can't see its caller's variables.
+pub(super) struct EvalFnBuilder {}
+impl BuiltinFnBuilder for EvalFnBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["eval"]
+    }
+
+    fn doc() -> &'static str {
+        "`(eval form env)`: evaluates `form` in a fresh scope with only the \
+         builtins, and the entries of the optional record `env` (pass the \
+         caller's variables that `form` needs there)."
+    }
+
+    fn arguments() -> Vec<&'static str> {
+        vec!["form", "env"]
+    }
+
+    fn arity() -> Arity {
+        Arity::Between(1, 2)
+    }
+
+    fn eval(args: &SExpr, ctx: &mut Context) -> EResult<Var> {
+        let mut scope = ctx.root().child();
+        if let Some(env) = args.get(1) {
+            let Record(mapping) = env.as_ref() else {
+                return Err(EvalError::Type {
+                    expected: "Record".to_string(),
+                    actual: env.type_str().to_string(),
+                });
+            };
+            for (name, value) in mapping.iter() {
+                scope.set(name, value.clone())?;
+            }
+        }
+        crate::eval(args.first().unwrap(), &mut scope, ctx)
+    }
+}
+
+pub(super) struct ApplyFnBuilder {}
+impl BuiltinFnBuilder for ApplyFnBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["apply"]
+    }
+
+    fn doc() -> &'static str {
+        "`(apply f args)`: calls `f` with the items of the list `args` as its \
+         arguments."
+    }
+
+    fn arguments() -> Vec<&'static str> {
+        vec!["f", "args"]
+    }
+
+    fn arity() -> Arity {
+        Arity::Fixed(2)
+    }
+
+    fn eval(args: &SExpr, ctx: &mut Context) -> EResult<Var> {
+        use crate::eval::eval_function;
+
+        let func = args.first().unwrap().expect_fn()?;
+        let func_args = args.get(1).unwrap().expect_sexp()?;
+        eval_function(func, func_args.to_vec(), ctx)
+    }
+}
+
+/***********************\
+|* Partial application *|
+\***********************/
+pub(super) struct PartialFnBuilder {}
+impl BuiltinFnBuilder for PartialFnBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["partial"]
+    }
+
+    fn doc() -> &'static str {
+        "`(partial f args...)`: `f` with its leading arguments bound to `args`."
+    }
+
+    fn arguments() -> Vec<&'static str> {
+        vec!["f", "args"]
+    }
+
+    fn arity() -> Arity {
+        Arity::AtLeast(1)
+    }
+
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
+        let func = args.first().unwrap().expect_fn()?;
+        Ok(func.partial(&args[1..])?.into())
+    }
+}
+
+pub(super) struct CurryFnBuilder {}
+impl BuiltinFnBuilder for CurryFnBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["curry"]
+    }
+
+    fn doc() -> &'static str {
+        "`(curry f)`: `f`, taking its arguments a few at a time: `((curry f) a)` \
+         is `(partial f a)`, until there are enough to call `f`."
+    }
+
+    fn arguments() -> Vec<&'static str> {
+        vec!["f"]
+    }
+
+    fn arity() -> Arity {
+        Arity::Fixed(1)
+    }
+
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
+        let func = args.first().unwrap().expect_fn()?;
+        Ok(func.curry()?.into())
+    }
+}
+
+/****************\
+|* Host objects *|
+\****************/
+pub(super) struct SendFnBuilder {}
+impl BuiltinFnBuilder for SendFnBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["send"]
+    }
+
+    fn doc() -> &'static str {
+        "`(send object :method args...)`: calls a method the host registered for \
+         the type of host object `object`."
+    }
+
+    fn arguments() -> Vec<&'static str> {
+        vec!["object", "method", "args"]
+    }
+
+    fn arity() -> Arity {
+        Arity::AtLeast(2)
+    }
+
+    fn eval(args: &SExpr, ctx: &mut Context) -> EResult<Var> {
+        use crate::eval::eval_function;
+
+        let object = args[0].expect_host()?;
+        let name = args[1].expect_keyword()?;
+        let method = ctx
+            .method(object, name)
+            .cloned()
+            .ok_or_else(|| {
+                EvalError::UnknownMethod {
+                    type_name: object.type_name().to_string(),
+                    method: name.to_string(),
+                }
+            })?;
+        let mut method_args = vec![args[0].clone()];
+        method_args.extend(args[2..].iter().cloned());
+        eval_function(&method, method_args, ctx)
+    }
+}
+
+/************\
+|* Metadata *|
+\************/
+// Special forms and macros can't be passed to functions, so `doc` is a
+// special form (see `DocFormBuilder`), and `source` and `arglist` only take
+// functions.
+
+fn nil_or(value: Option<Var>) -> Var {
+    value.unwrap_or_else(|| Value::Nil.into())
+}
+
+pub(super) struct SourceFnBuilder {}
+impl BuiltinFnBuilder for SourceFnBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["source"]
+    }
+
+    fn doc() -> &'static str {
+        "`(source f)`: the form that defined function `f` (nil for builtins)."
+    }
+
+    fn arguments() -> Vec<&'static str> {
+        vec!["f"]
+    }
+
+    fn arity() -> Arity {
+        Arity::Fixed(1)
+    }
+
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
+        let func = args.first().unwrap().expect_fn()?;
+        Ok(nil_or(func.meta.source.clone()))
+    }
+}
+
+pub(super) struct ArglistFnBuilder {}
+impl BuiltinFnBuilder for ArglistFnBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["arglist"]
+    }
+
+    fn doc() -> &'static str {
+        "`(arglist f)`: the parameters of function `f`, as a lambda list (a list \
+         of them for `case-lambda` functions)."
+    }
+
+    fn arguments() -> Vec<&'static str> {
+        vec!["f"]
+    }
+
+    fn arity() -> Arity {
+        Arity::Fixed(1)
+    }
+
+    fn eval(args: &SExpr, _ctx: &mut Context) -> EResult<Var> {
+        let func = args.first().unwrap().expect_fn()?;
+        let lambda_list = |func: &Function| -> Var {
+            Expr::SExpr(func.params.lambda_list()).into()
+        };
+        Ok(match &func.form {
+            CallForm::Overloaded(clauses) => {
+                Expr::SExpr(
+                    clauses
+                        .iter()
+                        .map(lambda_list)
+                        .collect(),
+                )
+                .into()
+            },
+            _ => lambda_list(func),
+        })
+    }
+}
+
 /************\
 |* Equality *|
 \************/
@@ -418,6 +875,10 @@ impl BuiltinFnBuilder for EqFnBuilder {
         vec!["eq", "=="]
     }
 
+    fn doc() -> &'static str {
+        "`(eq a b)`: whether `a` and `b` are equal."
+    }
+
     fn arguments() -> Vec<&'static str> {
         vec!["lhs", "rhs"]
     }
@@ -426,8 +887,8 @@ impl BuiltinFnBuilder for EqFnBuilder {
         Arity::Fixed(2)
     }
 
-    fn eval(sexpr: &SExpr) -> EResult<Var> {
-        let lhs = sexpr.get(0).unwrap();
+    fn eval(sexpr: &SExpr, _ctx: &mut Context) -> EResult<Var> {
+        let lhs = sexpr.first().unwrap();
         let rhs = sexpr.get(1).unwrap();
         Ok(Value::Bool(lhs == rhs).into())
     }
@@ -439,6 +900,10 @@ impl BuiltinFnBuilder for NeqFnBuilder {
         vec!["ne", "!="]
     }
 
+    fn doc() -> &'static str {
+        "`(ne a b)`: whether `a` and `b` are different."
+    }
+
     fn arguments() -> Vec<&'static str> {
         vec!["lhs", "rhs"]
     }
@@ -447,8 +912,8 @@ impl BuiltinFnBuilder for NeqFnBuilder {
         Arity::Fixed(2)
     }
 
-    fn eval(sexpr: &SExpr) -> EResult<Var> {
-        let lhs = sexpr.get(0).unwrap();
+    fn eval(sexpr: &SExpr, _ctx: &mut Context) -> EResult<Var> {
+        let lhs = sexpr.first().unwrap();
         let rhs = sexpr.get(1).unwrap();
         Ok(Value::Bool(lhs != rhs).into())
     }
@@ -460,6 +925,10 @@ impl BuiltinFnBuilder for NegateFnBuilder {
         vec!["negate", "!"]
     }
 
+    fn doc() -> &'static str {
+        "`(negate b)`: the opposite of the boolean `b`."
+    }
+
     fn arguments() -> Vec<&'static str> {
         vec!["val"]
     }
@@ -468,8 +937,8 @@ impl BuiltinFnBuilder for NegateFnBuilder {
         Arity::Fixed(1)
     }
 
-    fn eval(sexpr: &SExpr) -> EResult<Var> {
-        let var = sexpr.get(0).unwrap();
+    fn eval(sexpr: &SExpr, _ctx: &mut Context) -> EResult<Var> {
+        let var = sexpr.first().unwrap();
         if let Expr::Value(Value::Bool(val)) = var.as_ref() {
             Ok(Value::Bool(!val).into())
         } else {
@@ -8,9 +8,31 @@ pub fn builtins() -> Scope {
 
     // special forms
     special_forms::QuoteFormBuilder::register(&mut scope);
+    special_forms::QuasiquoteFormBuilder::register(&mut scope);
     special_forms::LambdaFormBuilder::register(&mut scope);
+    special_forms::CaseLambdaFormBuilder::register(&mut scope);
     special_forms::DefVarForm::register(&mut scope);
     special_forms::DefineFormBuilder::register(&mut scope);
+    special_forms::LetFormBuilder::register(&mut scope);
+    special_forms::LetStarFormBuilder::register(&mut scope);
+    special_forms::LetrecFormBuilder::register(&mut scope);
+    special_forms::DeclareFormBuilder::register(&mut scope);
+    special_forms::FreeVarsFormBuilder::register(&mut scope);
+    special_forms::DefDynamicFormBuilder::register(&mut scope);
+    special_forms::ParameterizeFormBuilder::register(&mut scope);
+    special_forms::SetFormBuilder::register(&mut scope);
+    special_forms::DefMacroFormBuilder::register(&mut scope);
+    special_forms::DefineSyntaxFormBuilder::register(&mut scope);
+    special_forms::MacroExpand1FormBuilder::register(&mut scope);
+    special_forms::MacroExpandFormBuilder::register(&mut scope);
+    special_forms::DocFormBuilder::register(&mut scope);
+    special_forms::DoFormBuilder::register(&mut scope);
+    special_forms::IfFormBuilder::register(&mut scope);
+    special_forms::CondFormBuilder::register(&mut scope);
+    special_forms::WhenFormBuilder::register(&mut scope);
+    special_forms::UnlessFormBuilder::register(&mut scope);
+    special_forms::AndFormBuilder::register(&mut scope);
+    special_forms::OrFormBuilder::register(&mut scope);
 
     // functions
     functions::IdentityFnBuilder::register(&mut scope);
@@ -20,12 +42,25 @@ pub fn builtins() -> Scope {
     functions::FirstFnBuilder::register(&mut scope);
     functions::RestFnBuilder::register(&mut scope);
     functions::ConcatFnBuilder::register(&mut scope);
+    functions::ListFnBuilder::register(&mut scope);
     functions::RecordFnBuilder::register(&mut scope);
     functions::RangeFnBuilder::register(&mut scope);
     functions::MapFnBuilder::register(&mut scope);
+    functions::EvalFnBuilder::register(&mut scope);
+    functions::ApplyFnBuilder::register(&mut scope);
+    functions::PartialFnBuilder::register(&mut scope);
+    functions::CurryFnBuilder::register(&mut scope);
+    functions::SendFnBuilder::register(&mut scope);
+    functions::SourceFnBuilder::register(&mut scope);
+    functions::ArglistFnBuilder::register(&mut scope);
     functions::EqFnBuilder::register(&mut scope);
     functions::NeqFnBuilder::register(&mut scope);
     functions::NegateFnBuilder::register(&mut scope);
+    functions::CapturesFnBuilder::register(&mut scope);
+    functions::BoxFnBuilder::register(&mut scope);
+    functions::UnboxFnBuilder::register(&mut scope);
+    functions::SetBoxFnBuilder::register(&mut scope);
+    functions::SwapFnBuilder::register(&mut scope);
 
     scope
 }
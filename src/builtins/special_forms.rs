@@ -1,17 +1,17 @@
-use std::sync::Mutex;
-
-use lazy_static::lazy_static;
+use std::rc::Rc;
 
 use crate::ast::{
-    Arity, CallForm, Expr, Function, SExpr, SpecialForm, Value, Var,
+    Arity, CallForm, DynamicVar, Expr, Function, KeywordParam, Macro,
+    MacroExpander, Metadata, OptionalParam, Origin, OwnedSExpr, Parameters,
+    Pattern, RecursiveGroup, SExpr, SpecialForm, SyntaxRules, Value, Var,
 };
-use crate::{EResult, EvalError, Scope, eval};
+use crate::{BindingKind, Context, EResult, EvalError, Scope, eval};
 
-/** See also:
+/* See also:
   - https://clojure.org/reference/special_forms#var
   - https://docs.racket-lang.org/reference/syntax.html
   - http://www.lispworks.com/documentation/HyperSpec/Body/03_ababa.htm
-**/
+*/
 
 /// Helper trait for defining built-in special forms.
 /// Note: currently we don't instantiate structs for any of these,
@@ -22,24 +22,31 @@ pub(super) trait BuiltinSpecialBuilder {
         let form: Var = Expr::Special(SpecialForm {
             name: names.first().unwrap().to_string(),
             arity: Self::arity(),
+            doc: Self::doc(),
             eval: Self::eval,
             bind_outer_scope: Self::bind_outer_scope,
+            bind_body_outer_scope: Self::bind_body_outer_scope,
         })
         .into();
 
         names
             .into_iter()
-            .for_each(|s| scope.set(s, form.clone()))
+            .for_each(|s| scope.set_unchecked(s, form.clone()))
     }
 
     /// the built-in names that refer to this special form
     fn names() -> Vec<&'static str>;
 
+    /// what the form does, for `doc`: the user-facing documentation, so the
+    /// builder's own doc comment only holds implementation notes
+    fn doc() -> &'static str;
+
     /// variadic or fixed arity
     fn arity() -> Arity;
 
-    /// called with list of arguments and enclosing scope
-    fn eval(sexpr: &SExpr, scope: &mut Scope) -> EResult<Var>;
+    /// called with list of arguments, enclosing scope and the interpreter
+    /// context
+    fn eval(sexpr: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var>;
 
     /// Builds the scope in which to evaluated this forms' arguments,
     /// if applicable. This is *early* binding - given the outer scope,
@@ -53,13 +60,73 @@ pub(super) trait BuiltinSpecialBuilder {
     ///  - `scope` - input: the enclosing scope.
     ///  - `capture_scope` - in/out: the captured scope for evaluating the form's
     ///    arguments
+    ///  - `ctx` - the interpreter context, for expanding macros
     fn bind_outer_scope(
         _args: &SExpr,
         _scope: &Scope,
         _capture_scope: &mut Scope,
+        _ctx: &mut Context,
     ) -> EResult<()> {
         Ok(())
     }
+
+    /// Like `bind_outer_scope`, when the form is called directly in a body
+    /// (see `eval::bind_body_outer_scope`). Definitions implement this to
+    /// declare the names they bind in `body_scope`, the body's own analysis
+    /// scope, so that later forms in the body don't capture them.
+    ///
+    /// By default, the same as `bind_outer_scope`.
+    fn bind_body_outer_scope(
+        args: &SExpr,
+        body_scope: &mut Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        Self::bind_outer_scope(args, body_scope, capture_scope, ctx)
+    }
+}
+
+/******************************\
+|* "do" special form impl     *|
+\******************************/
+pub(super) struct DoFormBuilder;
+impl BuiltinSpecialBuilder for DoFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["do", "begin"]
+    }
+
+    fn doc() -> &'static str {
+        "`(do exprs...)`: evaluates each expression in order, in the current \
+         scope, returning the value of the last one."
+    }
+
+    fn arity() -> Arity {
+        Arity::Variadic
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        eval::eval_body(args, scope, ctx)
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        eval::bind_body_outer_scope(args, scope, capture_scope, ctx)
+    }
+
+    /// Evaluated in the enclosing body's scope, so definitions are local to
+    /// that body
+    fn bind_body_outer_scope(
+        args: &SExpr,
+        body_scope: &mut Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        eval::bind_body_forms(args, body_scope, capture_scope, ctx)
+    }
 }
 
 /******************************\
@@ -71,22 +138,21 @@ impl BuiltinSpecialBuilder for IfFormBuilder {
         vec!["if"]
     }
 
+    fn doc() -> &'static str {
+        "`(if test then else)`: evaluates `then` if `test` is true, `else` \
+         otherwise."
+    }
+
     fn arity() -> Arity {
         Arity::Fixed(3)
     }
 
     /// Evaluate 1st argument then _either_ the 2nd or 3rd argument, not both
-    fn eval(args: &SExpr, scope: &mut Scope) -> EResult<Var> {
-        let determinant = eval(args.first().unwrap(), scope)?;
-        let Expr::Value(Value::Bool(result)) = determinant.as_ref() else {
-            return Err(EvalError::Type {
-                expected: "Bool".to_string(),
-                actual: determinant.type_str().to_string(),
-            });
-        };
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        let result = eval_condition(args.first().unwrap(), scope, ctx)?;
 
-        let idx: usize = if *result { 1 } else { 2 };
-        eval(args.get(idx).unwrap(), scope)
+        let idx: usize = if result { 1 } else { 2 };
+        eval(args.get(idx).unwrap(), scope, ctx)
     }
 
     /// capture references for all arguments
@@ -94,11 +160,269 @@ impl BuiltinSpecialBuilder for IfFormBuilder {
         args: &SExpr,
         scope: &Scope,
         capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        eval::bind_sexpr_outer_scope(args, scope, capture_scope, ctx)
+    }
+}
+
+/******************************\
+|* "cond" special form impl   *|
+\******************************/
+pub(super) struct CondFormBuilder;
+impl CondFormBuilder {
+    fn get_clause(clause: &Var) -> EResult<(&Var, &SExpr)> {
+        match clause.as_ref() {
+            Expr::SExpr(items) if items.len() >= 2 => {
+                Ok((&items[0], &items[1..]))
+            },
+            _ => {
+                Err(EvalError::Syntax {
+                    expected: "cond clause: (test exprs...)".to_string(),
+                    actual: clause.to_string(),
+                })
+            },
+        }
+    }
+
+    fn is_else(test: &Var) -> bool {
+        matches!(test.as_ref(), Expr::Symbol(s) if s == "else")
+    }
+}
+
+impl BuiltinSpecialBuilder for CondFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["cond"]
+    }
+
+    fn doc() -> &'static str {
+        "`(cond (test exprs...)... (else exprs...))`: evaluates the expressions \
+         of the first clause whose test is true (nil if there's none)."
+    }
+
+    fn arity() -> Arity {
+        Arity::Variadic
+    }
+
+    fn eval(
+        clauses: &SExpr,
+        scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<Var> {
+        for clause in clauses.iter() {
+            let (test, body) = Self::get_clause(clause)?;
+            if Self::is_else(test) || eval_condition(test, scope, ctx)? {
+                return eval::eval_body(body, scope, ctx);
+            }
+        }
+        Ok(Expr::Value(Value::Nil).into())
+    }
+
+    /// capture references for all tests and branches (like `when`, what a
+    /// branch defines is local to it)
+    fn bind_outer_scope(
+        clauses: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        for clause in clauses.iter() {
+            let (test, body) = Self::get_clause(clause)?;
+            if !Self::is_else(test) {
+                eval::bind_outer_scope(test, scope, capture_scope, ctx)?;
+            }
+            eval::bind_body_outer_scope(body, scope, capture_scope, ctx)?;
+        }
+        Ok(())
+    }
+}
+
+/**************************************\
+|* "when"/"unless" special forms impl *|
+\**************************************/
+pub(super) struct WhenFormBuilder;
+impl WhenFormBuilder {
+    fn eval_when(
+        expected: bool,
+        args: &SExpr,
+        scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<Var> {
+        if eval_condition(args.first().unwrap(), scope, ctx)? == expected {
+            eval::eval_body(&args[1..], scope, ctx)
+        } else {
+            Ok(Expr::Value(Value::Nil).into())
+        }
+    }
+
+    /// The body may not run, so what it defines is local to it
+    fn bind_when_outer_scope(
+        args: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        eval::bind_outer_scope(
+            args.first().unwrap(),
+            scope,
+            capture_scope,
+            ctx,
+        )?;
+        eval::bind_body_outer_scope(&args[1..], scope, capture_scope, ctx)
+    }
+}
+
+impl BuiltinSpecialBuilder for WhenFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["when"]
+    }
+
+    fn doc() -> &'static str {
+        "`(when test exprs...)`: evaluates the expressions if `test` is true, \
+         returning nil otherwise."
+    }
+
+    fn arity() -> Arity {
+        Arity::AtLeast(2)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        Self::eval_when(true, args, scope, ctx)
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        WhenFormBuilder::bind_when_outer_scope(args, scope, capture_scope, ctx)
+    }
+}
+
+pub(super) struct UnlessFormBuilder;
+impl BuiltinSpecialBuilder for UnlessFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["unless"]
+    }
+
+    fn doc() -> &'static str {
+        "`(unless test exprs...)`: evaluates the expressions if `test` is false, \
+         returning nil otherwise."
+    }
+
+    fn arity() -> Arity {
+        Arity::AtLeast(2)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        WhenFormBuilder::eval_when(false, args, scope, ctx)
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        WhenFormBuilder::bind_when_outer_scope(args, scope, capture_scope, ctx)
+    }
+}
+
+/**********************************\
+|* "and"/"or" special forms impl  *|
+\**********************************/
+pub(super) struct AndFormBuilder;
+impl AndFormBuilder {
+    /// Evaluate the arguments until one of them is `short_circuit`
+    fn eval_until(
+        short_circuit: bool,
+        args: &SExpr,
+        scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<Var> {
+        for arg in args.iter() {
+            if eval_condition(arg, scope, ctx)? == short_circuit {
+                return Ok(Expr::Value(Value::Bool(short_circuit)).into());
+            }
+        }
+        Ok(Expr::Value(Value::Bool(!short_circuit)).into())
+    }
+}
+
+impl BuiltinSpecialBuilder for AndFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["and"]
+    }
+
+    fn doc() -> &'static str {
+        "`(and exprs...)`: whether all the expressions are true, evaluating them \
+         only until one is false. `(and)` is true."
+    }
+
+    fn arity() -> Arity {
+        Arity::Variadic
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        Self::eval_until(false, args, scope, ctx)
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        eval::bind_sexpr_outer_scope(args, scope, capture_scope, ctx)
+    }
+}
+
+pub(super) struct OrFormBuilder;
+impl BuiltinSpecialBuilder for OrFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["or"]
+    }
+
+    fn doc() -> &'static str {
+        "`(or exprs...)`: whether any of the expressions is true, evaluating \
+         them only until one is true. `(or)` is false."
+    }
+
+    fn arity() -> Arity {
+        Arity::Variadic
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        AndFormBuilder::eval_until(true, args, scope, ctx)
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
     ) -> EResult<()> {
-        eval::bind_sexpr_outer_scope(args, scope, capture_scope)
+        eval::bind_sexpr_outer_scope(args, scope, capture_scope, ctx)
     }
 }
 
+/// Evaluate a condition, which must be a Bool
+fn eval_condition(
+    var: &Var,
+    scope: &mut Scope,
+    ctx: &mut Context,
+) -> EResult<bool> {
+    let determinant = eval(var, scope, ctx)?;
+    let Expr::Value(Value::Bool(result)) = determinant.as_ref() else {
+        return Err(EvalError::Type {
+            expected: "Bool".to_string(),
+            actual: determinant.type_str().to_string(),
+        });
+    };
+    Ok(*result)
+}
+
 /******************************\
 |* "Quote" special form impl *|
 \******************************/
@@ -108,11 +432,19 @@ impl BuiltinSpecialBuilder for QuoteFormBuilder {
         vec!["quote"]
     }
 
+    fn doc() -> &'static str {
+        "`(quote items...)`: the list of the items, unevaluated."
+    }
+
     fn arity() -> Arity {
         Arity::Variadic
     }
 
-    fn eval(args: &SExpr, _scope: &mut Scope) -> EResult<Var> {
+    fn eval(
+        args: &SExpr,
+        _scope: &mut Scope,
+        _ctx: &mut Context,
+    ) -> EResult<Var> {
         Ok(Expr::SExpr(Vec::from(args)).into())
     }
 
@@ -124,25 +456,132 @@ impl BuiltinSpecialBuilder for QuoteFormBuilder {
     // ) -> EResult<()> {panic!("at the disco")}
 }
 
+/***********************************\
+|* "quasiquote" special form impl  *|
+\***********************************/
+/// Handy for building code in macros, e.g.
+///     `(quasiquote if (unquote c) (do (unquote-splicing body)) nil)`
+/// Quasiquotes don't nest: an inner `quasiquote` is just a symbol.
+pub(super) struct QuasiquoteFormBuilder;
+impl QuasiquoteFormBuilder {
+    /// The argument of `(op arg)`, if `var` is that form
+    fn unquoted<'a>(var: &'a Var, op: &str) -> Option<&'a Var> {
+        match var.as_ref() {
+            Expr::SExpr(items) if items.len() == 2 => {
+                match items[0].as_ref() {
+                    Expr::Symbol(s) if s == op => Some(&items[1]),
+                    _ => None,
+                }
+            },
+            _ => None,
+        }
+    }
+
+    fn fill(
+        template: &SExpr,
+        scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<OwnedSExpr> {
+        let mut filled = OwnedSExpr::new();
+        for var in template.iter() {
+            if let Some(expr) = Self::unquoted(var, "unquote-splicing") {
+                let value = eval(expr, scope, ctx)?;
+                filled.extend(value.expect_sexp()?.iter().cloned());
+            } else if let Some(expr) = Self::unquoted(var, "unquote") {
+                filled.push(eval(expr, scope, ctx)?);
+            } else if let Expr::SExpr(items) = var.as_ref() {
+                filled.push(Expr::SExpr(Self::fill(items, scope, ctx)?).into());
+            } else {
+                filled.push(var.clone());
+            }
+        }
+        Ok(filled)
+    }
+
+    fn bind_template_outer_scope(
+        template: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        for var in template.iter() {
+            if let Some(expr) = Self::unquoted(var, "unquote-splicing")
+                .or_else(|| Self::unquoted(var, "unquote"))
+            {
+                eval::bind_outer_scope(expr, scope, capture_scope, ctx)?;
+            } else if let Expr::SExpr(items) = var.as_ref() {
+                Self::bind_template_outer_scope(
+                    items,
+                    scope,
+                    capture_scope,
+                    ctx,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BuiltinSpecialBuilder for QuasiquoteFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["quasiquote"]
+    }
+
+    fn doc() -> &'static str {
+        "`(quasiquote items...)`: like `quote`, but evaluates `(unquote x)` and \
+         splices in the list `(unquote-splicing xs)`."
+    }
+
+    fn arity() -> Arity {
+        Arity::Variadic
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        Ok(Expr::SExpr(Self::fill(args, scope, ctx)?).into())
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        Self::bind_template_outer_scope(args, scope, capture_scope, ctx)
+    }
+}
+
 /******************************\
 |* "defvar" special form impl *|
 \******************************/
+/// The pattern may be any destructuring `Pattern`, e.g.
+///     `(defvar (a b) (quote 1 2))`
+///
+/// Definitions bind names in the current scope. Inside a function (or `let`)
+/// body, that's the body's own scope: the names are visible to later forms in
+/// the body, including closures created there, but not to the caller. As with
+/// any other local, closures in the body never capture the outer binding of a
+/// name once it's been defined locally.
 pub(super) struct DefVarForm;
 impl BuiltinSpecialBuilder for DefVarForm {
     fn names() -> Vec<&'static str> {
         vec!["defvar"]
     }
 
+    fn doc() -> &'static str {
+        "`(defvar pattern value)`: binds `value` to `pattern` in the current \
+         scope."
+    }
+
     fn arity() -> Arity {
         Arity::Fixed(2)
     }
 
-    fn eval(args: &SExpr, scope: &mut Scope) -> EResult<Var> {
-        let symbol_name = args.get(0).unwrap().expect_symbol()?;
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        let pattern = Pattern::from_expr(args.first().unwrap())?;
         let body = args.get(1).unwrap();
 
-        let value = eval(body, scope)?;
-        scope.set(symbol_name, value);
+        let value = eval(body, scope, ctx)?;
+        pattern.bind(&value, scope)?;
 
         Ok(Var::new(Expr::empty()))
     }
@@ -151,22 +590,38 @@ impl BuiltinSpecialBuilder for DefVarForm {
         args: &SExpr,
         scope: &Scope,
         capture_scope: &mut Scope,
+        ctx: &mut Context,
     ) -> EResult<()> {
-        let symbol = args.get(0).unwrap();
-        let symbol_name = symbol.expect_symbol()?;
+        let pattern = Pattern::from_expr(args.first().unwrap())?;
         let rhs = args.get(1).unwrap();
 
+        // this would fail at runtime (see `Scope::set`), so fail early
+        if let Some(value) = rhs
+            .expect_symbol()
+            .ok()
+            .and_then(|name| scope.lookup(name))
+        {
+            if let Some(special) = value.special_name() {
+                return Err(EvalError::AliasedSpecial {
+                    name: pattern.to_string(),
+                    special: special.to_string(),
+                });
+            }
+        }
+
         // capture any variables necessary to evaluate the RHS
-        eval::bind_outer_scope(rhs, scope, capture_scope)?;
+        eval::bind_outer_scope(rhs, scope, capture_scope, ctx)
+    }
 
-        // if not already part of the closure, make this
-        // symbol its own _Symbol_ in the capture scope (?)
-        // TODO: this is probably incorrect if you are, for instance, running
-        //      (define) within the scope of a thing.
-        if !capture_scope.has(symbol_name) {
-            capture_scope.set(symbol_name, symbol.clone());
-        }
-        Ok(())
+    fn bind_body_outer_scope(
+        args: &SExpr,
+        body_scope: &mut Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        Self::bind_outer_scope(args, body_scope, capture_scope, ctx)?;
+        let pattern = Pattern::from_expr(args.first().unwrap())?;
+        declare_definitions(body_scope, pattern.names())
     }
 }
 
@@ -174,67 +629,127 @@ impl BuiltinSpecialBuilder for DefVarForm {
 |* "Define" special form impl *|
 \******************************/
 /// Binds symbols to expressions in the current scope.
-/// - If the first argument is a symbol, evaluates the second argument then binds
-///   it to the symbol.
-/// 2) If the second argument is an S-expr of symbols, it defines a function
-/// Specifically, the following two expressions are equivalent:
-///     `(define (f a1 a2 ...) (b0 b1 b2 ...))`
-///     `(define f (lambda (a1 a2 ...) (b0 b1 b2 ...)))`
+/// 1) If the first argument is a symbol, evaluates the second argument then binds
+///    it to the symbol.
+/// 2) If the first argument is an S-expr of symbols, it defines a function whose
+///    body is the remaining arguments
+/// 3) If the first argument is a symbol followed by several `(params body)`
+///    clauses, or by a single list of them, it defines a multi-arity function. A
+///    lone clause can't be told apart from a call, so `(define f ([x] x))` is
+///    case 1: use `(define f (([x] x)))` or `(define (f x) x)` instead.
+/// 4) If the first argument is a record pattern, destructures the second argument
+///    like `defvar`
+///
+/// Specifically, the following pairs of expressions are equivalent:
+///     `(define (f a1 a2 ...) (b0 b1 b2 ...) ...)`
+///     `(define f (lambda (a1 a2 ...) (b0 b1 b2 ...) ...))`
+/// and
+///     `(define f ([a1] (b0 ...)) ([a1 a2] (b0 ...)))`
+///     `(define f (([a1] (b0 ...)) ([a1 a2] (b0 ...))))`
+///     `(define f (case-lambda ([a1] (b0 ...)) ([a1 a2] (b0 ...))))`
+/// except that functions defined with `define` can call themselves (see
+/// `RecursiveGroup`).
 pub(super) struct DefineFormBuilder;
+impl DefineFormBuilder {
+    /// The function name in a `(define (name params...) body...)` form
+    fn get_fn_name(sexp: &SExpr) -> EResult<&str> {
+        sexp.first()
+            .ok_or(EvalError::Syntax {
+                expected: "List of symbols".to_string(),
+                actual: "Empty".to_string(),
+            })?
+            .expect_symbol()
+    }
+
+    /// The clauses of a multi-arity `(define name clauses...)` form, which may
+    /// also be wrapped in a single list, or None if it binds a plain value
+    fn get_clauses(args: &SExpr) -> Option<&SExpr> {
+        let is_clause = |var: &Var| {
+            match var.as_ref() {
+                Expr::SExpr(items) => {
+                    matches!(
+                        items.first().map(|var| var.as_ref()),
+                        Some(Expr::SExpr(_))
+                    )
+                },
+                _ => false,
+            }
+        };
+        match args.get(1).map(|var| var.as_ref()) {
+            _ if args.len() > 2 => Some(&args[1..]),
+            Some(Expr::SExpr(items))
+                if !items.is_empty() && items.iter().all(is_clause) =>
+            {
+                Some(items)
+            },
+            _ => None,
+        }
+    }
+}
+
 impl BuiltinSpecialBuilder for DefineFormBuilder {
     fn names() -> Vec<&'static str> {
         vec!["def", "define"]
     }
 
+    fn doc() -> &'static str {
+        "`(define name value)` binds a value; `(define (name params...) \
+         'docstring' body...)` defines a function."
+    }
+
     fn arity() -> Arity {
-        Arity::Fixed(2)
+        Arity::AtLeast(2)
     }
 
-    fn eval(args: &SExpr, scope: &mut Scope) -> EResult<Var> {
-        let lhs = args.get(0).unwrap();
-        let rhs = args.get(1).unwrap();
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        let lhs = args.first().unwrap();
+
+        match (lhs.as_ref(), Self::get_clauses(args)) {
+            // treat as equivalent to (defvar #0 (case-lambda ...))
+            (Expr::Symbol(name), Some(clauses)) => {
+                let form = CaseLambdaFormBuilder::build_function(
+                    name.clone(),
+                    clauses,
+                    form_source("define", args),
+                    &mut placeholder_scope(scope, [name.as_str()])?,
+                    ctx,
+                )?;
+                define_recursive(scope, name, form)?;
+
+                Ok(Expr::empty().into())
+            },
 
-        match lhs.as_ref() {
             // treat as equivalent to DefVar
-            Expr::Symbol(_name) => DefVarForm::eval(args, scope),
+            (Expr::Symbol(_) | Expr::Record(_), _) if args.len() == 2 => {
+                DefVarForm::eval(args, scope, ctx)
+            },
 
             // treat as equivalent to (defvar #0 (lambda ...))
-            Expr::SExpr(sexp) => {
-                // get the function name
-                let fn_name = sexp
-                    .first()
-                    .ok_or(EvalError::Syntax {
-                        expected: "List of symbols".to_string(),
-                        actual: "Empty".to_string(),
-                    })?
-                    .expect_symbol()?;
+            (Expr::SExpr(sexp), _) => {
+                let fn_name = Self::get_fn_name(sexp)?;
 
                 // arguments for `lambda`
-                let lambda_args = vec![
-                    Expr::SExpr(
-                        sexp[1..]
-                            .iter()
-                            .map(Var::clone)
-                            .collect(),
-                    )
-                    .into(),
-                    rhs.clone(),
-                ];
+                let lambda_args: OwnedSExpr =
+                    [Expr::SExpr(sexp[1..].to_vec()).into()]
+                        .into_iter()
+                        .chain(args[1..].iter().cloned())
+                        .collect();
 
                 // move new function into scope
                 let form = LambdaFormBuilder::build_function(
                     fn_name.to_string(),
                     &lambda_args,
-                    scope,
+                    form_source("define", args),
+                    &mut placeholder_scope(scope, [fn_name])?,
+                    ctx,
                 )?;
-
-                scope.set(fn_name, form);
+                define_recursive(scope, fn_name, form)?;
 
                 Ok(Expr::empty().into())
             },
             _other => {
                 Err(EvalError::Syntax {
-                    expected: "S-Expression or Symbol".to_string(),
+                    expected: "S-Expression, Symbol or Record".to_string(),
                     actual: lhs.type_str().to_string(),
                 })
             },
@@ -245,84 +760,241 @@ impl BuiltinSpecialBuilder for DefineFormBuilder {
         args: &SExpr,
         scope: &Scope,
         capture_scope: &mut Scope,
+        ctx: &mut Context,
     ) -> EResult<()> {
         // TODO: this is almost an exact duplicate of eval, except it has
         // different args
-        let lhs = args.get(0).unwrap();
-        let rhs = args.get(1).unwrap();
+        let lhs = args.first().unwrap();
 
-        match lhs.as_ref() {
-            Expr::Symbol(_name) => {
-                DefVarForm::bind_outer_scope(args, scope, capture_scope)
-            },
-            Expr::SExpr(sexp) => {
-                let lambda_args =
-                    vec![Var::new(Expr::SExpr(sexp[1..].to_vec())), rhs.clone()];
+        match (lhs.as_ref(), Self::get_clauses(args)) {
+            (Expr::Symbol(name), Some(clauses)) => {
+                CaseLambdaFormBuilder::bind_outer_scope(
+                    clauses,
+                    &placeholder_scope(scope, [name.as_str()])?,
+                    capture_scope,
+                    ctx,
+                )
+            },
+            (Expr::Symbol(_) | Expr::Record(_), _) if args.len() == 2 => {
+                DefVarForm::bind_outer_scope(args, scope, capture_scope, ctx)
+            },
+            (Expr::SExpr(sexp), _) => {
+                let fn_name = Self::get_fn_name(sexp)?;
+                let lambda_args: OwnedSExpr =
+                    [Expr::SExpr(sexp[1..].to_vec()).into()]
+                        .into_iter()
+                        .chain(args[1..].iter().cloned())
+                        .collect();
                 LambdaFormBuilder::bind_outer_scope(
                     &lambda_args,
-                    scope,
+                    &placeholder_scope(scope, [fn_name])?,
                     capture_scope,
+                    ctx,
                 )
             },
             _other => {
                 Err(EvalError::Syntax {
-                    expected: "S-Expression or Symbol".to_string(),
+                    expected: "S-Expression, Symbol or Record".to_string(),
                     actual: lhs.type_str().to_string(),
                 })
             },
         }
     }
+
+    fn bind_body_outer_scope(
+        args: &SExpr,
+        body_scope: &mut Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        Self::bind_outer_scope(args, body_scope, capture_scope, ctx)?;
+        let lhs = args.first().unwrap();
+        match lhs.as_ref() {
+            Expr::SExpr(sexp) => {
+                declare_definitions(body_scope, [Self::get_fn_name(sexp)?])
+            },
+            _ => {
+                declare_definitions(body_scope, Pattern::from_expr(lhs)?.names())
+            },
+        }
+    }
 }
 
 /******************************\
 |* "Lambda" special form impl *|
 \******************************/
-/// Given the argument list in a function/lambda declaration.
-/// 1) check that it is in fact a list of symbol names, and then
-/// 2) return them as a vector
-/// TODO: this might need to go somewhere more public?
 pub(super) struct LambdaFormBuilder;
 
 impl LambdaFormBuilder {
-    fn get_argnames(expr: &Expr) -> Result<Vec<String>, EvalError> {
-        expr.expect_sexp().and_then(|sexpr| {
-            sexpr
-                .iter()
-                .map(|expr| expr.expect_symbol().map(String::from))
-                .collect()
+    /// Given the argument list in a function/lambda declaration.
+    /// 1) check that it is in fact a lambda list (see `Parameters`), and then
+    /// 2) return it as `Parameters`
+    ///
+    /// TODO: this might need to go somewhere more public?
+    fn get_params(expr: &Expr) -> Result<Parameters, EvalError> {
+        let mut params = Parameters::default();
+        let mut section = ParamSection::Positional;
+
+        for var in expr.expect_sexp()?.iter() {
+            // section markers
+            if let Expr::Symbol(name) = var.as_ref() {
+                let next_section = match name.as_str() {
+                    "&opt" => Some(ParamSection::Optional),
+                    "&" => Some(ParamSection::Rest),
+                    "&key" => Some(ParamSection::Keyword),
+                    _ => None,
+                };
+                if let Some(next_section) = next_section {
+                    if next_section <= section {
+                        return Err(param_syntax_error(
+                            "`&opt`, `&` and `&key` once each, in that order",
+                            var,
+                        ));
+                    }
+                    if section == ParamSection::Rest && params.rest.is_none() {
+                        return Err(param_syntax_error("a name after `&`", var));
+                    }
+                    section = next_section;
+                    continue;
+                }
+            }
+
+            match section {
+                ParamSection::Positional => {
+                    params
+                        .positional
+                        .push(Pattern::from_expr(var)?);
+                },
+                ParamSection::Optional => {
+                    let (name, default) = Self::get_param_with_default(var)?;
+                    params
+                        .optional
+                        .push(OptionalParam { name, default });
+                },
+                ParamSection::Rest => {
+                    if params.rest.is_some() {
+                        return Err(param_syntax_error(
+                            "exactly one name after `&`",
+                            var,
+                        ));
+                    }
+                    params.rest = Some(var.expect_symbol()?.to_string());
+                },
+                ParamSection::Keyword => {
+                    params
+                        .keyword
+                        .push(Self::get_keyword_param(var)?);
+                },
+            }
+        }
+
+        if section == ParamSection::Rest && params.rest.is_none() {
+            return Err(EvalError::Syntax {
+                expected: "exactly one name after `&`".to_string(),
+                actual: "nothing".to_string(),
+            });
+        }
+        Ok(params)
+    }
+
+    /// `name` or `(name default)`
+    fn get_param_with_default(var: &Var) -> EResult<(String, Option<Var>)> {
+        match var.as_ref() {
+            Expr::Symbol(name) => Ok((name.clone(), None)),
+            Expr::SExpr(sexpr) => {
+                let [name, default] = sexpr.as_slice() else {
+                    return Err(param_syntax_error("(name default)", var));
+                };
+                Ok((
+                    name.expect_symbol()?.to_string(),
+                    Some(default.clone()),
+                ))
+            },
+            _ => {
+                Err(param_syntax_error(
+                    "name or (name default)",
+                    var,
+                ))
+            },
+        }
+    }
+
+    /// Like `get_param_with_default`, but may start with a `:label` if it's
+    /// different from the name: `(:label name default)`
+    fn get_keyword_param(var: &Var) -> EResult<KeywordParam> {
+        if let Expr::SExpr(sexpr) = var.as_ref() {
+            if let [label, name, rest @ ..] = sexpr.as_slice() {
+                if let Expr::Keyword(label) = label.as_ref() {
+                    let default = match rest {
+                        [] => None,
+                        [default] => Some(default.clone()),
+                        _ => {
+                            return Err(param_syntax_error(
+                                "(:label name default)",
+                                var,
+                            ));
+                        },
+                    };
+                    return Ok(KeywordParam {
+                        label: label.clone(),
+                        name: name.expect_symbol()?.to_string(),
+                        default,
+                    });
+                }
+            }
+        }
+
+        let (name, default) = Self::get_param_with_default(var)?;
+        Ok(KeywordParam {
+            label: name.clone(),
+            name,
+            default,
         })
     }
 
+    /// `source` is the whole form defining the function, for its metadata
     fn build_function(
         name: String,
         sexpr: &SExpr,
+        source: Var,
         scope: &mut Scope,
+        ctx: &mut Context,
     ) -> EResult<Var> {
         // capture references to outer scope
-        let mut capture_scope = Scope::new(None);
-        LambdaFormBuilder::bind_outer_scope(sexpr, scope, &mut capture_scope)?;
-
-        // create function object
-        let argnames = Self::get_argnames(sexpr.get(0).unwrap())?;
-        let body = sexpr.get(1).unwrap().expect_sexp()?;
-        Ok(Var::new(
-            Function {
-                name,
-                arity: Arity::Fixed(argnames.len()),
-                arguments: argnames,
-                form: CallForm::Lambda {
-                    sexpr: Vec::from(body),
-                    scope: capture_scope,
-                },
-            }
-            .into(),
-        ))
+        let mut capture_scope = Scope::captures();
+        LambdaFormBuilder::bind_outer_scope(
+            sexpr,
+            scope,
+            &mut capture_scope,
+            ctx,
+        )?;
+
+        let meta = definition_metadata(source, ctx);
+        Ok(Self::build_clause(name, sexpr, capture_scope, meta)?.into())
     }
-}
 
-// a counter for generating unique names for our lambdas
-lazy_static! {
-    static ref LAMBDA_COUNTER: Mutex<usize> = Mutex::new(0);
+    /// Create the function object for a `(params 'docstring' body)` clause,
+    /// given its already-captured scope and the metadata of its definition
+    fn build_clause(
+        name: String,
+        sexpr: &SExpr,
+        capture_scope: Scope,
+        meta: Metadata,
+    ) -> EResult<Function> {
+        let params = Self::get_params(sexpr.first().unwrap())?;
+        let (doc, body) = split_docstring(&sexpr[1..]);
+        Ok(Function {
+            name,
+            arity: params.arity(),
+            params,
+            form: CallForm::Lambda {
+                body: body.to_vec(),
+                scope: capture_scope,
+                group: None,
+            },
+            meta: Rc::new(Metadata { doc, ..meta }),
+        })
+    }
 }
 
 impl BuiltinSpecialBuilder for LambdaFormBuilder {
@@ -330,20 +1002,25 @@ impl BuiltinSpecialBuilder for LambdaFormBuilder {
         vec!["lambda", "λ"]
     }
 
-    fn arity() -> Arity {
-        Arity::Fixed(2)
+    fn doc() -> &'static str {
+        "`(lambda (params...) 'docstring' body...)`: a function, capturing the \
+         values it uses from the enclosing scope."
     }
 
-    fn eval(sexpr: &SExpr, scope: &mut Scope) -> EResult<Var> {
-        // TODO: deal with LAMBDA_COUNTER overflow
-        // (should just restart at 0)
-        let name = {
-            let mut count = LAMBDA_COUNTER.lock().unwrap();
-            *count += 1;
-            format!("λ_{count}")
-        };
+    /// `(lambda params body...)`
+    fn arity() -> Arity {
+        Arity::AtLeast(2)
+    }
 
-        Self::build_function(name, sexpr, scope)
+    fn eval(sexpr: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        let source = form_source("lambda", sexpr);
+        Self::build_function(
+            ctx.next_lambda_name(),
+            sexpr,
+            source,
+            scope,
+            ctx,
+        )
     }
 
     /// find names of outer vars that this thing requires.
@@ -356,83 +1033,1156 @@ impl BuiltinSpecialBuilder for LambdaFormBuilder {
         sexpr: &SExpr,
         outer_scope: &Scope,
         capture_scope: &mut Scope,
+        ctx: &mut Context,
     ) -> Result<(), EvalError> {
         // get arguments and function body
-        let argnames = Self::get_argnames(sexpr.get(0).unwrap())?;
-        let body = sexpr.get(1).unwrap().expect_sexp()?;
+        let params = Self::get_params(sexpr.first().unwrap())?;
+        let body = &sexpr[1..];
+
+        // Parameters are bound in order, so each default can only refer to
+        // the parameters before it.
+        let mut child_outer = outer_scope.child();
+        for name in params
+            .positional
+            .iter()
+            .flat_map(Pattern::names)
+        {
+            child_outer.declare(name)?;
+        }
+        for param in params.optional.iter() {
+            if let Some(default) = &param.default {
+                eval::bind_outer_scope(
+                    default,
+                    &child_outer,
+                    capture_scope,
+                    ctx,
+                )?;
+            }
+            child_outer.declare(&param.name)?;
+        }
+        if let Some(rest) = &params.rest {
+            child_outer.declare(rest)?;
+        }
+        for param in params.keyword.iter() {
+            if let Some(default) = &param.default {
+                eval::bind_outer_scope(
+                    default,
+                    &child_outer,
+                    capture_scope,
+                    ctx,
+                )?;
+            }
+            child_outer.declare(&param.name)?;
+        }
+
+        eval::bind_body_outer_scope(body, &child_outer, capture_scope, ctx)
+    }
+}
+
+/// The form `(head args...)`, e.g. to record a function's source
+fn form_source(head: &str, args: &SExpr) -> Var {
+    let mut form = vec![Expr::Symbol(head.to_string()).into()];
+    form.extend(args.iter().cloned());
+    Expr::SExpr(form).into()
+}
+
+/// The metadata of a function defined by `source`. It originates on the line
+/// the form was parsed from, or where evaluation currently is if it was built
+/// at runtime (e.g. by a macro)
+fn definition_metadata(source: Var, ctx: &Context) -> Metadata {
+    let parsed_line = source
+        .expect_sexp()
+        .ok()
+        .and_then(|form| form.iter().find_map(Var::line));
+    let origin = ctx.location().map(|location| {
+        Origin {
+            file: location.file.clone(),
+            line: parsed_line.unwrap_or(location.line),
+        }
+    });
+    Metadata {
+        doc: None,
+        origin,
+        source: Some(source),
+        parent: ctx
+            .current_function()
+            .map(str::to_string),
+    }
+}
+
+/// Split off a leading docstring. A lone string is a value, not a docstring.
+fn split_docstring(body: &SExpr) -> (Option<String>, &SExpr) {
+    match body {
+        [first, rest @ ..] if !rest.is_empty() => {
+            match first.as_ref() {
+                Expr::Value(Value::Str(doc)) => (Some(doc.clone()), rest),
+                _ => (None, body),
+            }
+        },
+        _ => (None, body),
+    }
+}
+
+/***********************************\
+|* "case-lambda" special form impl *|
+\***********************************/
+/// E.g.
+///     `(case-lambda ([x] x) ([x y] (+ x y)))`
+/// Each clause is a `(params body...)` list, as in `lambda`. All clauses
+/// share one capture scope.
+pub(super) struct CaseLambdaFormBuilder;
+
+impl CaseLambdaFormBuilder {
+    fn get_clause(clause: &Var) -> EResult<&SExpr> {
+        match clause.as_ref() {
+            Expr::SExpr(items) if items.len() >= 2 => Ok(items),
+            _ => {
+                Err(EvalError::Syntax {
+                    expected: "clause: (params body...)".to_string(),
+                    actual: clause.to_string(),
+                })
+            },
+        }
+    }
+
+    /// `source` is the whole form defining the function, for its metadata
+    fn build_function(
+        name: String,
+        clauses: &SExpr,
+        source: Var,
+        scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<Var> {
+        let mut capture_scope = Scope::captures();
+        Self::bind_outer_scope(clauses, scope, &mut capture_scope, ctx)?;
+
+        let meta = definition_metadata(source, ctx);
+        let (doc, clauses) = split_docstring(clauses);
+        let clauses = clauses
+            .iter()
+            .map(|clause| {
+                LambdaFormBuilder::build_clause(
+                    name.clone(),
+                    Self::get_clause(clause)?,
+                    capture_scope.clone(),
+                    meta.clone(),
+                )
+            })
+            .collect::<EResult<Vec<Function>>>()?;
+
+        Ok(Function {
+            name,
+            arity: Arity::OneOf(
+                clauses
+                    .iter()
+                    .map(|clause| clause.arity.clone())
+                    .collect(),
+            ),
+            params: Parameters::default(),
+            form: CallForm::Overloaded(clauses),
+            meta: Rc::new(Metadata { doc, ..meta }),
+        }
+        .into())
+    }
+}
+
+impl BuiltinSpecialBuilder for CaseLambdaFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["case-lambda"]
+    }
+
+    fn doc() -> &'static str {
+        "`(case-lambda 'docstring' ((params...) body...)...)`: a function that \
+         calls the first clause accepting its arguments."
+    }
+
+    fn arity() -> Arity {
+        Arity::AtLeast(1)
+    }
+
+    fn eval(
+        clauses: &SExpr,
+        scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<Var> {
+        let source = form_source("case-lambda", clauses);
+        Self::build_function(
+            ctx.next_lambda_name(),
+            clauses,
+            source,
+            scope,
+            ctx,
+        )
+    }
+
+    fn bind_outer_scope(
+        clauses: &SExpr,
+        outer_scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        let (_, clauses) = split_docstring(clauses);
+        for clause in clauses.iter() {
+            LambdaFormBuilder::bind_outer_scope(
+                Self::get_clause(clause)?,
+                outer_scope,
+                capture_scope,
+                ctx,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/*********************************************\
+|* "let", "let*" and "letrec" special forms  *|
+\*********************************************/
+/// Local bindings, e.g.
+///     `(let ((x 1) ((y z) (quote 2 3))) (print x) (+ x y))`
+/// Each binding is a `(pattern init)` pair. The forms differ in where the
+/// `init` expressions are evaluated:
+/// - `let`: all in the enclosing scope, so they can't see each other;
+/// - `let*`: in order, each seeing the bindings before it;
+/// - `letrec`: in order, with all of the names already in scope. Functions
+///   defined this way can call each other (see `RecursiveGroup`).
+pub(super) struct LetFormBuilder;
+
+/// How the bindings of a `let`-style form see each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LetKind {
+    Parallel,
+    Sequential,
+    Recursive,
+}
+
+impl LetFormBuilder {
+    fn get_bindings(var: &Var) -> EResult<Vec<(Pattern, Var)>> {
+        var.expect_sexp()?
+            .iter()
+            .map(|binding| {
+                match binding.as_ref() {
+                    Expr::SExpr(items) if items.len() == 2 => {
+                        Ok((
+                            Pattern::from_expr(&items[0])?,
+                            items[1].clone(),
+                        ))
+                    },
+                    _ => {
+                        Err(EvalError::Syntax {
+                            expected: "binding: (pattern init)".to_string(),
+                            actual: binding.to_string(),
+                        })
+                    },
+                }
+            })
+            .collect()
+    }
+
+    fn eval_let(
+        kind: LetKind,
+        args: &SExpr,
+        scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<Var> {
+        let bindings = Self::get_bindings(args.first().unwrap())?;
+        let body = &args[1..];
+
+        let mut let_scope = match kind {
+            LetKind::Recursive => {
+                let mut let_scope = scope.local_child();
+                for name in bindings
+                    .iter()
+                    .flat_map(|(p, _)| p.names())
+                {
+                    let_scope.declare(name)?;
+                }
+                let_scope
+            },
+            _ => scope.local_child(),
+        };
+        if kind == LetKind::Parallel {
+            let values = bindings
+                .iter()
+                .map(|(_, init)| eval(init, scope, ctx))
+                .collect::<EResult<Vec<Var>>>()?;
+            for ((pattern, _), value) in bindings.iter().zip(values.iter()) {
+                pattern.bind(value, &mut let_scope)?;
+            }
+        } else {
+            for (pattern, init) in bindings.iter() {
+                let value = eval(init, &mut let_scope, ctx)?;
+                pattern.bind(&value, &mut let_scope)?;
+            }
+        }
+        if kind == LetKind::Recursive {
+            let templates = let_scope.clone();
+            bind_group(
+                &mut let_scope,
+                &templates,
+                bindings
+                    .iter()
+                    .flat_map(|(p, _)| p.names()),
+            )?;
+        }
+
+        eval::eval_body(body, &mut let_scope, ctx)
+    }
+
+    fn bind_let_outer_scope(
+        kind: LetKind,
+        args: &SExpr,
+        outer_scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        let bindings = Self::get_bindings(args.first().unwrap())?;
+        let body = &args[1..];
 
         let mut child_outer = outer_scope.child();
-        for name in argnames.into_iter() {
-            child_outer.set(&name, Expr::Symbol(name.clone()).into())
+        if kind == LetKind::Recursive {
+            for name in bindings
+                .iter()
+                .flat_map(|(p, _)| p.names())
+            {
+                child_outer.declare(name)?;
+            }
+        }
+        for (pattern, init) in bindings.iter() {
+            let init_scope = match kind {
+                LetKind::Parallel => outer_scope,
+                LetKind::Sequential | LetKind::Recursive => &child_outer,
+            };
+            eval::bind_outer_scope(init, init_scope, capture_scope, ctx)?;
+            if kind == LetKind::Sequential {
+                for name in pattern.names() {
+                    child_outer.declare(name)?;
+                }
+            }
+        }
+        if kind == LetKind::Parallel {
+            for name in bindings
+                .iter()
+                .flat_map(|(p, _)| p.names())
+            {
+                child_outer.declare(name)?;
+            }
+        }
+
+        eval::bind_body_outer_scope(body, &child_outer, capture_scope, ctx)
+    }
+}
+
+impl BuiltinSpecialBuilder for LetFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["let"]
+    }
+
+    fn doc() -> &'static str {
+        "`(let ((name value)...) body...)`: evaluates `body` with the names \
+         bound to the values."
+    }
+
+    fn arity() -> Arity {
+        Arity::AtLeast(2)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        Self::eval_let(LetKind::Parallel, args, scope, ctx)
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        outer_scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        Self::bind_let_outer_scope(
+            LetKind::Parallel,
+            args,
+            outer_scope,
+            capture_scope,
+            ctx,
+        )
+    }
+}
+
+pub(super) struct LetStarFormBuilder;
+impl BuiltinSpecialBuilder for LetStarFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["let*"]
+    }
+
+    fn doc() -> &'static str {
+        "`(let* ((name value)...) body...)`: like `let`, but each value can \
+         refer to the names before it."
+    }
+
+    fn arity() -> Arity {
+        Arity::AtLeast(2)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        LetFormBuilder::eval_let(LetKind::Sequential, args, scope, ctx)
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        outer_scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        LetFormBuilder::bind_let_outer_scope(
+            LetKind::Sequential,
+            args,
+            outer_scope,
+            capture_scope,
+            ctx,
+        )
+    }
+}
+
+pub(super) struct LetrecFormBuilder;
+impl BuiltinSpecialBuilder for LetrecFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["letrec"]
+    }
+
+    fn doc() -> &'static str {
+        "`(letrec ((name value)...) body...)`: like `let`, but the values can \
+         refer to each other, e.g. for mutually recursive functions."
+    }
+
+    fn arity() -> Arity {
+        Arity::AtLeast(2)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        LetFormBuilder::eval_let(LetKind::Recursive, args, scope, ctx)
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        outer_scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        LetFormBuilder::bind_let_outer_scope(
+            LetKind::Recursive,
+            args,
+            outer_scope,
+            capture_scope,
+            ctx,
+        )
+    }
+}
+
+/*********************************\
+|* "declare" special form impl   *|
+\*********************************/
+/// Mutually recursive definitions, e.g.
+///     `(declare (even? odd?)
+///         (define (even? n) (if (eq n 0) true (odd? (+ n -1))))
+///         (define (odd? n) (if (eq n 0) false (even? (+ n -1)))))`
+/// The forms are evaluated in order, in a child scope where the declared names
+/// are already in scope. Then the declared names (and only those) are bound
+/// in the current scope, as a single `RecursiveGroup`.
+pub(super) struct DeclareFormBuilder;
+impl DeclareFormBuilder {
+    fn get_names(var: &Var) -> EResult<Vec<&str>> {
+        var.expect_sexp()?
+            .iter()
+            .map(|name| name.expect_symbol())
+            .collect()
+    }
+}
+
+impl BuiltinSpecialBuilder for DeclareFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["declare"]
+    }
+
+    fn doc() -> &'static str {
+        "`(declare (names...) definitions...)`: evaluates definitions that can \
+         refer to each other, and binds the declared names."
+    }
+
+    fn arity() -> Arity {
+        Arity::AtLeast(1)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        let names = Self::get_names(args.first().unwrap())?;
+
+        let mut group_scope = placeholder_scope(scope, names.iter().copied())?;
+        eval::eval_body(&args[1..], &mut group_scope, ctx)?;
+        bind_group(scope, &group_scope, names)?;
+
+        Ok(Expr::empty().into())
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        outer_scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        let names = Self::get_names(args.first().unwrap())?;
+
+        eval::bind_body_outer_scope(
+            &args[1..],
+            &placeholder_scope(outer_scope, names.iter().copied())?,
+            capture_scope,
+            ctx,
+        )
+    }
+
+    fn bind_body_outer_scope(
+        args: &SExpr,
+        body_scope: &mut Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        Self::bind_outer_scope(args, body_scope, capture_scope, ctx)?;
+        declare_definitions(
+            body_scope,
+            Self::get_names(args.first().unwrap())?,
+        )
+    }
+}
+
+/***********************************\
+|* "free-vars" special form impl   *|
+\***********************************/
+/// Runs the same analysis as `lambda`, without building a closure. E.g.
+///     `(free-vars (quote + x y))`
+/// gives `x`, `y` and `+`.
+pub(super) struct FreeVarsFormBuilder;
+impl BuiltinSpecialBuilder for FreeVarsFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["free-vars"]
+    }
+
+    fn doc() -> &'static str {
+        "`(free-vars expr)`: the names a closure with body `expr` would capture."
+    }
+
+    fn arity() -> Arity {
+        Arity::Fixed(1)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        let expr = eval(args.first().unwrap(), scope, ctx)?;
+
+        // analyze in a throwaway child so definitions inside `expr` don't
+        // leak placeholders into the caller's scope
+        let mut analysis_scope = scope.child();
+        let mut capture_scope = Scope::captures();
+        eval::bind_body_forms(
+            std::slice::from_ref(&expr),
+            &mut analysis_scope,
+            &mut capture_scope,
+            ctx,
+        )?;
+        Ok(Expr::Record(capture_scope.values()).into())
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        eval::bind_body_outer_scope(args, scope, capture_scope, ctx)
+    }
+}
+
+/*************************************\
+|* "defdynamic" special form impl    *|
+\*************************************/
+/// The variable is a `DynamicVar`: closures capture it rather than its
+/// value, so they see whatever `parameterize` binds it to when they run.
+pub(super) struct DefDynamicFormBuilder;
+impl BuiltinSpecialBuilder for DefDynamicFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["defdynamic"]
+    }
+
+    fn doc() -> &'static str {
+        "`(defdynamic name value)`: defines a dynamic variable: closures see its \
+         current value, not the one it had when they were created."
+    }
+
+    fn arity() -> Arity {
+        Arity::Fixed(2)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        let name = args.first().unwrap().expect_symbol()?;
+        let value = eval(args.get(1).unwrap(), scope, ctx)?;
+
+        // cells are only ever read through `eval`, which would hand the
+        // special form out as a value
+        if let Some(special) = value.special_name() {
+            return Err(EvalError::AliasedSpecial {
+                name: name.to_string(),
+                special: special.to_string(),
+            });
         }
 
-        eval::bind_sexpr_outer_scope(body, &child_outer, capture_scope)
+        scope.set(
+            name,
+            Expr::Dynamic(DynamicVar::new(name, value)).into(),
+        )?;
+        Ok(Var::new(Expr::empty()))
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        eval::bind_outer_scope(
+            args.get(1).unwrap(),
+            scope,
+            capture_scope,
+            ctx,
+        )
+    }
+
+    fn bind_body_outer_scope(
+        args: &SExpr,
+        body_scope: &mut Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        Self::bind_outer_scope(args, body_scope, capture_scope, ctx)?;
+        let name = args.first().unwrap().expect_symbol()?;
+        declare_definitions(body_scope, [name])
+    }
+}
+
+/*************************************\
+|* "parameterize" special form impl  *|
+\*************************************/
+/// The old values are restored even if the body fails. The values are all
+/// evaluated before any of the variables are rebound.
+pub(super) struct ParameterizeFormBuilder;
+impl ParameterizeFormBuilder {
+    fn get_bindings(var: &Var) -> EResult<Vec<(&Var, &Var)>> {
+        var.expect_sexp()?
+            .iter()
+            .map(|binding| {
+                match binding.as_ref() {
+                    Expr::SExpr(items) if items.len() == 2 => {
+                        Ok((&items[0], &items[1]))
+                    },
+                    _ => {
+                        Err(EvalError::Syntax {
+                            expected: "binding: (dynamic-var value)".to_string(),
+                            actual: binding.to_string(),
+                        })
+                    },
+                }
+            })
+            .collect()
+    }
+
+    fn lookup_dynamic(name: &Var, scope: &Scope) -> EResult<Rc<DynamicVar>> {
+        let var = scope.lookup_or_error(name.expect_symbol()?)?;
+        match var.as_ref() {
+            Expr::Dynamic(cell) => Ok(cell.clone()),
+            other => {
+                Err(EvalError::Type {
+                    expected: "Dynamic".to_string(),
+                    actual: other.type_str().to_string(),
+                })
+            },
+        }
+    }
+}
+
+impl BuiltinSpecialBuilder for ParameterizeFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["parameterize", "binding"]
+    }
+
+    fn doc() -> &'static str {
+        "`(parameterize ((name value)...) body...)`: evaluates `body` with the \
+         dynamic variables rebound to the values."
+    }
+
+    fn arity() -> Arity {
+        Arity::AtLeast(2)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        let bindings = Self::get_bindings(args.first().unwrap())?
+            .into_iter()
+            .map(|(name, value)| {
+                Ok((
+                    Self::lookup_dynamic(name, scope)?,
+                    eval(value, scope, ctx)?,
+                ))
+            })
+            .collect::<EResult<Vec<_>>>()?;
+
+        let saved: Vec<_> = bindings
+            .into_iter()
+            .map(|(cell, value)| {
+                let old = cell.replace(value);
+                (cell, old)
+            })
+            .collect();
+
+        let result = eval::eval_body(&args[1..], scope, ctx);
+
+        // restore in reverse, in case a variable was rebound twice
+        for (cell, old) in saved.into_iter().rev() {
+            cell.replace(old);
+        }
+        result
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        for (name, value) in Self::get_bindings(args.first().unwrap())? {
+            eval::bind_outer_scope(name, scope, capture_scope, ctx)?;
+            eval::bind_outer_scope(value, scope, capture_scope, ctx)?;
+        }
+        eval::bind_body_outer_scope(&args[1..], scope, capture_scope, ctx)
     }
 }
 
 /******************************\
-|* BELOW: unimplemented ideas *|
+|* "set!" special form impl    *|
 \******************************/
+/// See `Scope::assign`.
+///
+/// Closures capture copies of plain values, so a closure can only `set!` its
+/// own parameters and locals: anything it would capture is an error when the
+/// closure is created. Dynamic variables are captured as the variable itself,
+/// so `set!` changes its current value. To share mutable state between
+/// closures, use a box.
+pub(super) struct SetFormBuilder;
+impl BuiltinSpecialBuilder for SetFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["set!"]
+    }
+
+    fn doc() -> &'static str {
+        "`(set! name value)`: changes the value of an existing binding (not a \
+         captured one)."
+    }
+
+    fn arity() -> Arity {
+        Arity::Fixed(2)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        let name = args.first().unwrap().expect_symbol()?;
+        let value = eval(args.get(1).unwrap(), scope, ctx)?;
+
+        scope.assign(name, value)?;
+        Ok(Var::new(Expr::empty()))
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        let target = args.first().unwrap();
+        let name = target.expect_symbol()?;
+
+        let binding = scope
+            .lookup_binding(name)
+            .ok_or_else(|| EvalError::LookupError(name.to_string()))?;
+        match binding.value().map(|var| var.as_ref()) {
+            // bound when the closure runs
+            None => {},
+            Some(Expr::Dynamic(_)) => {
+                eval::bind_outer_scope(target, scope, capture_scope, ctx)?
+            },
+            Some(expr) if expr.special_name().is_some() => {
+                return Err(EvalError::ShadowedSpecial(name.to_string()));
+            },
+            Some(_) => return Err(EvalError::AssignedCapture(name.to_string())),
+        }
+
+        eval::bind_outer_scope(
+            args.get(1).unwrap(),
+            scope,
+            capture_scope,
+            ctx,
+        )
+    }
+}
+
+/********************************\
+|* "defmacro" special form impl *|
+\********************************/
+/// The expander is a function of the unevaluated arguments (see `Macro`),
+/// e.g.
+///     `(defmacro my-when (c & body)
+///         (quasiquote if (unquote c) (do (unquote-splicing body)) nil))`
+///
+/// Calls are expanded right before they're evaluated, and closure capture
+/// analysis runs on the expanded code. So that the analysis always knows
+/// which macros are in scope, macros can only be defined at the top level.
+pub(super) struct DefMacroFormBuilder;
+impl BuiltinSpecialBuilder for DefMacroFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["defmacro"]
+    }
+
+    fn doc() -> &'static str {
+        "`(defmacro name (params...) 'docstring' body...)`: a macro, computing \
+         the form to evaluate from its unevaluated arguments."
+    }
+
+    fn arity() -> Arity {
+        Arity::AtLeast(3)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        let name = args.first().unwrap().expect_symbol()?;
+        check_macro_definition("defmacro", name, scope)?;
+
+        let expander = LambdaFormBuilder::build_function(
+            name.to_string(),
+            &args[1..],
+            form_source("defmacro", args),
+            scope,
+            ctx,
+        )?
+        .expect_fn()?
+        .clone();
+        let mac = Macro {
+            name: name.to_string(),
+            meta: expander.meta.clone(),
+            expander: MacroExpander::Procedure(expander),
+        };
+        scope.set_unchecked(name, Expr::Macro(mac).into());
+
+        Ok(Var::new(Expr::empty()))
+    }
+
+    fn bind_outer_scope(
+        _args: &SExpr,
+        _scope: &Scope,
+        _capture_scope: &mut Scope,
+        _ctx: &mut Context,
+    ) -> EResult<()> {
+        Err(macro_not_top_level("defmacro"))
+    }
+}
+
+/*************************************\
+|* "define-syntax" special form impl *|
+\*************************************/
+/// See `SyntaxRules`. E.g.
+///     `(define-syntax my-or
+///         (syntax-rules ()
+///           ((_) false)
+///           ((_ e) e)
+///           ((_ e rest ...) (let ((t e)) (if t t (my-or rest ...))))))`
+/// As with `defmacro`, only at the top level.
+pub(super) struct DefineSyntaxFormBuilder;
+impl BuiltinSpecialBuilder for DefineSyntaxFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["define-syntax"]
+    }
+
+    fn doc() -> &'static str {
+        "`(define-syntax name 'docstring' (syntax-rules (literals...) (pattern \
+         template)...))`: a hygienic pattern-based macro."
+    }
 
-// IDEA: Keyword args with swift-style caller/callee names.
-//     They keyword arg is what the caller sees, but the variable name
-//     is what the function sees?
-//     See also Clojure version: https://clojure.org/news/2021/03/18/apis-serving-people-and-programs
-//     Dumb Q: should that go here or in the AST/parser?
-//     A: obviously here, we'll need to attach enough information
-//       to the function to remap the keyword args.
+    fn arity() -> Arity {
+        Arity::Between(2, 3)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        let name = args.first().unwrap().expect_symbol()?;
+        check_macro_definition("define-syntax", name, scope)?;
+
+        let (doc, spec) = split_docstring(&args[1..]);
+        let [spec] = spec else {
+            return Err(EvalError::Syntax {
+                expected: "(define-syntax name 'docstring' (syntax-rules ...))"
+                    .to_string(),
+                actual: form_source("define-syntax", args).to_string(),
+            });
+        };
+        let rules = match spec.as_ref() {
+            Expr::SExpr(items)
+                if matches!(
+                    items.first().map(|head| head.as_ref()),
+                    Some(Expr::Symbol(head)) if head == "syntax-rules"
+                ) =>
+            {
+                SyntaxRules::new(name, &items[1..], scope, ctx)?
+            },
+            _ => {
+                return Err(EvalError::Syntax {
+                    expected: "(syntax-rules (literals...) rules...)".to_string(),
+                    actual: spec.to_string(),
+                });
+            },
+        };
+        let meta = definition_metadata(form_source("define-syntax", args), ctx);
+        let mac = Macro {
+            name: name.to_string(),
+            expander: MacroExpander::Rules(Rc::new(rules)),
+            meta: Rc::new(Metadata { doc, ..meta }),
+        };
+        scope.set_unchecked(name, Expr::Macro(mac).into());
+
+        Ok(Var::new(Expr::empty()))
+    }
+
+    fn bind_outer_scope(
+        _args: &SExpr,
+        _scope: &Scope,
+        _capture_scope: &mut Scope,
+        _ctx: &mut Context,
+    ) -> EResult<()> {
+        Err(macro_not_top_level("define-syntax"))
+    }
+}
+
+/// Macros can only be defined at the top level, and can't replace builtin
+/// special forms (but may replace other macros)
+fn check_macro_definition(form: &str, name: &str, scope: &Scope) -> EResult<()> {
+    if scope.kind() != BindingKind::Global {
+        return Err(macro_not_top_level(form));
+    }
+    if let Some(existing) = scope.lookup(name) {
+        if let Expr::Special(_) = existing.as_ref() {
+            return Err(EvalError::ShadowedSpecial(name.to_string()));
+        }
+    }
+    Ok(())
+}
+
+fn macro_not_top_level(form: &str) -> EvalError {
+    EvalError::Syntax {
+        expected: format!("{form} at the top level"),
+        actual: format!("{form} in a function or let body"),
+    }
+}
+
+/*************************************\
+|* "macroexpand" special forms impl  *|
+\*************************************/
+pub(super) struct MacroExpand1FormBuilder;
+impl BuiltinSpecialBuilder for MacroExpand1FormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["macroexpand-1"]
+    }
+
+    fn doc() -> &'static str {
+        "`(macroexpand-1 form)`: evaluates `form`, then expands it once if it's \
+         a macro call, e.g. `(macroexpand-1 (quote my-when true 1))`."
+    }
+
+    fn arity() -> Arity {
+        Arity::Fixed(1)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        let form = eval(args.first().unwrap(), scope, ctx)?;
+        Ok(eval::macroexpand_1(&form, scope, ctx)?.unwrap_or(form))
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        eval::bind_body_outer_scope(args, scope, capture_scope, ctx)
+    }
+}
+
+pub(super) struct MacroExpandFormBuilder;
+impl BuiltinSpecialBuilder for MacroExpandFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["macroexpand"]
+    }
+
+    fn doc() -> &'static str {
+        "`(macroexpand form)`: like `macroexpand-1`, but keeps expanding until \
+         the result isn't a macro call. Subforms aren't expanded."
+    }
+
+    fn arity() -> Arity {
+        Arity::Fixed(1)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        let form = eval(args.first().unwrap(), scope, ctx)?;
+        eval::macroexpand(&form, scope, ctx)
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        eval::bind_body_outer_scope(args, scope, capture_scope, ctx)
+    }
+}
+
+/*****************************\
+|* "doc" special form impl   *|
+\*****************************/
+/// A special form rather than a function, so that it can document special
+/// forms and macros too: a symbol argument isn't evaluated if it names one.
+pub(super) struct DocFormBuilder;
+impl BuiltinSpecialBuilder for DocFormBuilder {
+    fn names() -> Vec<&'static str> {
+        vec!["doc"]
+    }
+
+    fn doc() -> &'static str {
+        "`(doc f)`: the docstring of `f`, a function, special form or macro (nil \
+         if it has none)."
+    }
+
+    fn arity() -> Arity {
+        Arity::Fixed(1)
+    }
+
+    fn eval(args: &SExpr, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
+        let arg = args.first().unwrap();
+        let target = match Self::named_special(arg, scope) {
+            Some(special) => special,
+            None => eval(arg, scope, ctx)?,
+        };
+        let doc = match target.as_ref() {
+            Expr::Special(special) => Some(special.doc.to_string()),
+            Expr::Macro(mac) => mac.meta.doc.clone(),
+            _ => target.expect_fn()?.meta.doc.clone(),
+        };
+        Ok(doc.map_or_else(
+            || Value::Nil.into(),
+            |doc| Value::Str(doc).into(),
+        ))
+    }
+
+    fn bind_outer_scope(
+        args: &SExpr,
+        scope: &Scope,
+        capture_scope: &mut Scope,
+        ctx: &mut Context,
+    ) -> EResult<()> {
+        let arg = args.first().unwrap();
+        match Self::named_special(arg, scope) {
+            Some(special) => {
+                capture_scope.set_unchecked(arg.expect_symbol()?, special);
+                Ok(())
+            },
+            None => eval::bind_outer_scope(arg, scope, capture_scope, ctx),
+        }
+    }
+}
+
+impl DocFormBuilder {
+    /// The special form or macro `arg` names, if it's a symbol naming one
+    fn named_special(arg: &Var, scope: &Scope) -> Option<Var> {
+        let Expr::Symbol(name) = arg.as_ref() else {
+            return None;
+        };
+        scope
+            .lookup(name)
+            .filter(|value| value.special_name().is_some())
+    }
+}
+
+/// Sections of a lambda list, in the order they must appear
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ParamSection {
+    Positional,
+    Optional,
+    Rest,
+    Keyword,
+}
+
+fn param_syntax_error(expected: &str, actual: &Var) -> EvalError {
+    EvalError::Syntax {
+        expected: format!("parameter: {expected}"),
+        actual: actual.to_string(),
+    }
+}
+
+/// A child of `scope` with `names` declared, so that closures analyzed in it
+/// don't capture them
+fn placeholder_scope<'a>(
+    scope: &Scope,
+    names: impl IntoIterator<Item = &'a str>,
+) -> EResult<Scope> {
+    let mut child = scope.child();
+    for name in names {
+        child.declare(name)?;
+    }
+    Ok(child)
+}
+
+/// Declare the names defined directly in a body in `body_scope`, the body's
+/// analysis scope (see `eval::bind_body_outer_scope`): they're local to the
+/// body from that point on, so later forms and closures in the body don't
+/// capture them
+fn declare_definitions<'a>(
+    body_scope: &mut Scope,
+    names: impl IntoIterator<Item = &'a str>,
+) -> EResult<()> {
+    for name in names {
+        body_scope.declare(name)?;
+    }
+    Ok(())
+}
+
+/// Bind `names`, as defined in `defined_in`, in `scope` as a single
+/// `RecursiveGroup`
+fn bind_group<'a>(
+    scope: &mut Scope,
+    defined_in: &Scope,
+    names: impl IntoIterator<Item = &'a str>,
+) -> EResult<()> {
+    let members = names
+        .into_iter()
+        .map(|name| {
+            // still just declared
+            let Some(template) = defined_in.lookup(name) else {
+                return Err(EvalError::Syntax {
+                    expected: format!("a definition for '{name}'"),
+                    actual: "none".to_string(),
+                });
+            };
+            Ok((name.to_string(), template))
+        })
+        .collect::<EResult<Vec<_>>>()?;
+    RecursiveGroup::new(members).bind(scope)
+}
+
+/// Bind a function that can call itself by `name`
+fn define_recursive(scope: &mut Scope, name: &str, template: Var) -> EResult<()> {
+    let group = RecursiveGroup::new(vec![(name.to_string(), template.clone())]);
+    scope.set(name, group.attach(&template))
+}
 
 /******************************\
-|* "special" builder form impl *|
+|* BELOW: unimplemented ideas *|
 \******************************/
 
-// `special` is a built-in special form creator, with nearly identical
-// syntax and semantics to the `lambda` form.
-// The differences are simply what happens later, when the newly defined
-// form or function is _called_:
-//  - unlike functions, arguments to special forms are _not_ evaluated when being
-//    sent to special forms
-// Q: should special forms receive the scope as a first class object?
-//    Do users need to provide a "close_over" method somehow?
-//    Are user-defined specials even a good idea? What can they do
-//    that `lambda`s operating on quoted arguments can't do?
-//
-// struct SpecialBuilderForm;
-// impl BuiltinSpecial for SpecialBuilderForm {
-//     fn names() -> Vec<&'static str> {
-//         vec!["special"]
-//     }
-//
-//     fn arity() -> Arity {
-//         Arity::Fixed(2)
-//     }
-//
-//     fn eval(sexpr: &SExpr, scope: &mut Scope) -> EResult {
-//         let argnames = get_argnames(sexpr.get(0).unwrap())?;
-//         let body = sexpr.get(1).unwrap().expect_sexp()?;
-//         let scope = LambdaForm::close_over(&body, &scope)?;
-//
-//         Ok(Var::new(
-//             SpecialForm {
-//                 name: "special".to_string(),
-//                 arity: Arity::Fixed(argnames.len()),
-//                 arguments: argnames,
-//                 form: CallForm::Lambda {
-//                     sexpr: Vec::from(body),
-//                     scope,
-//                 },
-//             }
-//             .into(),
-//         ))
-//     }
-//
-//     fn close_over(
-//         sexpr: &SExpr,
-//         outer_scope: &Scope,
-//     ) -> Result<Scope, EvalError> { todo!()
-//     }
-// }
-
 /*********************************\
 |* "Programme" special form impl *|
 \*********************************/
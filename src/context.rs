@@ -0,0 +1,142 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::ast::{Function, HostObject, Origin};
+use crate::{Scope, builtins};
+
+/// Interpreter state that evaluation, and in particular builtin functions,
+/// have access to (unlike the scope, which builtin functions never see).
+pub struct Context {
+    output: Output,
+    /// The builtins, which global scopes are children of
+    root: Scope,
+    /// For naming anonymous functions
+    lambda_count: usize,
+    /// For renaming the names introduced by `syntax-rules` expansions
+    expansion_count: usize,
+    /// Methods of host object types, by type and name
+    methods: HashMap<(TypeId, String), Function>,
+    /// Where the top-level form being evaluated starts, if known
+    location: Option<Origin>,
+    /// The names of the functions being called, innermost last
+    call_stack: Vec<String>,
+}
+
+/// Where `print` and friends write to
+enum Output {
+    Stdout(io::Stdout),
+    /// Kept in memory, see `Context::take_output`
+    Buffer(Vec<u8>),
+    Writer(Box<dyn Write>),
+}
+
+impl Context {
+    /// Writes output to stdout
+    pub fn new() -> Self {
+        Self::with_output(Output::Stdout(io::stdout()))
+    }
+
+    /// Keeps output in memory, to be retrieved with `take_output`
+    pub fn buffered() -> Self {
+        Self::with_output(Output::Buffer(Vec::new()))
+    }
+
+    /// Writes output to `writer`
+    pub fn with_writer(writer: impl Write + 'static) -> Self {
+        Self::with_output(Output::Writer(Box::new(writer)))
+    }
+
+    fn with_output(output: Output) -> Self {
+        Context {
+            output,
+            root: builtins(),
+            lambda_count: 0,
+            expansion_count: 0,
+            methods: HashMap::new(),
+            location: None,
+            call_stack: Vec::new(),
+        }
+    }
+
+    pub fn output(&mut self) -> &mut dyn Write {
+        match &mut self.output {
+            Output::Stdout(stdout) => stdout,
+            Output::Buffer(buffer) => buffer,
+            Output::Writer(writer) => writer.as_mut(),
+        }
+    }
+
+    /// The scope with the builtins: a fresh global scope is a child of it
+    pub fn root(&self) -> &Scope {
+        &self.root
+    }
+
+    /// A fresh name for an anonymous function
+    pub fn next_lambda_name(&mut self) -> String {
+        self.lambda_count = self.lambda_count.wrapping_add(1);
+        format!("λ_{}", self.lambda_count)
+    }
+
+    /// A fresh id for a macro expansion
+    pub fn next_expansion_id(&mut self) -> usize {
+        self.expansion_count = self.expansion_count.wrapping_add(1);
+        self.expansion_count
+    }
+
+    /// Make `method` callable on host objects holding a `T`, with
+    /// `(send object :name args...)`. It's called with the object as its
+    /// first argument.
+    pub fn register_method<T: Any>(&mut self, name: &str, method: Function) {
+        self.methods
+            .insert((TypeId::of::<T>(), name.to_string()), method);
+    }
+
+    /// The method `name` registered for the type of `object`
+    pub fn method(&self, object: &HostObject, name: &str) -> Option<&Function> {
+        self.methods
+            .get(&(object.value_type_id(), name.to_string()))
+    }
+
+    /// Set where the top-level form about to be evaluated starts, for the
+    /// metadata of the functions it defines
+    pub fn set_location(&mut self, location: Option<Origin>) {
+        self.location = location;
+    }
+
+    pub fn location(&self) -> Option<&Origin> {
+        self.location.as_ref()
+    }
+
+    /// Note that `name`'s body is being evaluated, until `leave_function`
+    pub fn enter_function(&mut self, name: &str) {
+        self.call_stack.push(name.to_string());
+    }
+
+    pub fn leave_function(&mut self) {
+        self.call_stack.pop();
+    }
+
+    /// The innermost function being called, if any
+    pub fn current_function(&self) -> Option<&str> {
+        self.call_stack
+            .last()
+            .map(String::as_str)
+    }
+
+    /// The output written so far, if it's buffered (empty otherwise)
+    pub fn take_output(&mut self) -> String {
+        match &mut self.output {
+            Output::Buffer(buffer) => {
+                String::from_utf8_lossy(&std::mem::take(buffer)).into_owned()
+            },
+            _ => String::new(),
+        }
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
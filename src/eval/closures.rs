@@ -1,5 +1,7 @@
+use super::eval_exprs::check_arity;
+use super::macros::expand_macro;
 use crate::ast::{Expr, SExpr, SpecialForm, Var};
-use crate::{EResult, Scope};
+use crate::{Context, EResult, EvalError, Scope};
 
 /// Lexical symbol binding for closures
 /// - i.e., captures variables from the enclosing scope.
@@ -12,10 +14,11 @@ pub fn bind_outer_scope(
     var: &Var,
     outer_scope: &Scope,
     capture_scope: &mut Scope,
+    ctx: &mut Context,
 ) -> EResult<()> {
     match var.as_ref() {
         Expr::SExpr(inner_sexpr) => {
-            bind_sexpr_outer_scope(inner_sexpr, outer_scope, capture_scope)
+            bind_sexpr_outer_scope(inner_sexpr, outer_scope, capture_scope, ctx)
         },
         Expr::Symbol(_) => {
             capture_symbol_reference(var, outer_scope, capture_scope)
@@ -24,37 +27,117 @@ pub fn bind_outer_scope(
     }
 }
 
+/// Like `bind_outer_scope`, for a sequence of expressions evaluated in order
+/// (see `eval_body`).
+///
+/// Definitions made directly in the body are local to it from that point on,
+/// so they're declared in a child of `outer_scope` for the body's own
+/// analysis. Definitions nested in other forms (e.g. `(when c (define x 1))`)
+/// may not run, so they're not: their names are looked up in the enclosing
+/// scopes, as if they weren't there.
+pub fn bind_body_outer_scope(
+    body: &SExpr,
+    outer_scope: &Scope,
+    capture_scope: &mut Scope,
+    ctx: &mut Context,
+) -> EResult<()> {
+    bind_body_forms(
+        body,
+        &mut outer_scope.child(),
+        capture_scope,
+        ctx,
+    )
+}
+
+/// Analyze the forms of a body in the body's own analysis scope, `body_scope`
+/// (see `bind_body_outer_scope`), declaring what they define in it
+pub fn bind_body_forms(
+    body: &SExpr,
+    body_scope: &mut Scope,
+    capture_scope: &mut Scope,
+    ctx: &mut Context,
+) -> EResult<()> {
+    for var in body.iter() {
+        let special = match var.as_ref() {
+            Expr::SExpr(sexpr) => special_call(sexpr, body_scope, capture_scope),
+            _ => None,
+        };
+        let Some((special_var, args)) = special else {
+            bind_outer_scope(var, body_scope, capture_scope, ctx)?;
+            continue;
+        };
+
+        if let Expr::Macro(mac) = special_var.as_ref() {
+            // the expansion takes the call's place in the body
+            let expansion = expand_macro(mac, args, ctx)?;
+            bind_body_forms(&[expansion], body_scope, capture_scope, ctx)?;
+            continue;
+        }
+
+        let special = expect_special_form(&special_var, args)?;
+        (special.bind_body_outer_scope)(args, body_scope, capture_scope, ctx)?;
+    }
+    Ok(())
+}
+
 pub fn bind_sexpr_outer_scope(
     sexpr: &SExpr,
     outer_scope: &Scope,
     capture_scope: &mut Scope,
+    ctx: &mut Context,
 ) -> EResult<()> {
-    if let Some((special_var, maybe_name)) = is_special(sexpr, outer_scope) {
-        let special: &SpecialForm = special_var.expect_special()?; // TODO: this should be an _internal_ error
-
-        if let Some(name) = maybe_name {
-            capture_scope.set(&name, special_var.clone())
+    if let Some((special_var, args)) =
+        special_call(sexpr, outer_scope, capture_scope)
+    {
+        if let Expr::Macro(mac) = special_var.as_ref() {
+            // analyze what the macro call will be replaced by
+            let expansion = expand_macro(mac, args, ctx)?;
+            return bind_outer_scope(&expansion, outer_scope, capture_scope, ctx);
         }
 
         // if s-expr is a special form, delegate to its bind_outer_scope method
-        (special.bind_outer_scope)(&sexpr[1..], outer_scope, capture_scope)
+        let special = expect_special_form(&special_var, args)?;
+        (special.bind_outer_scope)(args, outer_scope, capture_scope, ctx)
     } else {
         // capture references for each s-xep
         for var in sexpr.iter() {
-            bind_outer_scope(var, outer_scope, capture_scope)?
+            bind_outer_scope(var, outer_scope, capture_scope, ctx)?
         }
         Ok(())
     }
 }
 
+/// If `sexpr` is a call to a special form (builtin or macro), the form and
+/// the call's arguments. The form is captured under the name it was called
+/// by, if any.
+fn special_call<'a>(
+    sexpr: &'a SExpr,
+    scope: &Scope,
+    capture_scope: &mut Scope,
+) -> Option<(Var, &'a SExpr)> {
+    let (special_var, maybe_name) = is_special(sexpr, scope)?;
+    if let Some(name) = maybe_name {
+        capture_scope.set_unchecked(&name, special_var.clone())
+    }
+    Some((special_var, &sexpr[1..]))
+}
+
+/// The builtin special form `special_var`, checked against a call's `args`
+fn expect_special_form<'a>(
+    special_var: &'a Var,
+    args: &SExpr,
+) -> EResult<&'a SpecialForm> {
+    let special: &SpecialForm = special_var.expect_special()?; // TODO: this should be an _internal_ error
+    check_arity(&special.arity, &special.name, args.len())?;
+    Ok(special)
+}
+
 /// Capture a not-yet defined symbol from the outer scope,
 /// unless it will be provided as an argument.
 ///
-/// TODO: Right now, we signal that a symbol will be provided within the scope
-///     by making it tautological - i.e., equal to itself. This is cute and all
-///     but probably this should be signaled explicitly with a real sentinel;
-///     I'm sure this method breaks with some meta-programming edge case or
-///     something.
+/// Names that will be bound when the closure runs (parameters, local
+/// definitions...) are declared as `BindingKind::Local` placeholders while
+/// analyzing the closure, so they aren't captured.
 fn capture_symbol_reference(
     symbol: &Var,
     outer_scope: &Scope,
@@ -62,24 +145,24 @@ fn capture_symbol_reference(
 ) -> EResult<()> {
     let name = symbol.expect_symbol()?;
     if !capture_scope.has(name) {
-        let outer_val = outer_scope.lookup_or_error(name)?;
-
-        // don't capture it if it's tautological
-        if outer_val == *symbol {
-            return Ok(());
-        }
+        let binding = outer_scope
+            .lookup_binding(name)
+            .ok_or_else(|| EvalError::LookupError(name.to_string()))?;
 
         // otherwise add it to our collection of captured variables
-        capture_scope.set(name, outer_val)
+        if let Some(outer_val) = binding.value() {
+            capture_scope.set_unchecked(name, outer_val.clone())
+        }
     }
     Ok(())
 }
 
-/// check if an s-expression is a call to a special form.
+/// check if an s-expression is a call to a special form (builtin or macro).
 /// If so, return the form, and, if necessary, the symbol to bind the form to.
 ///
-/// Note that this takes advantage of the syntax rule that `Expr::Special` may
-/// not be aliased or shadowed, or returned from functions;
+/// Note that this takes advantage of the syntax rule that special forms may
+/// not be aliased or shadowed, or returned from functions (enforced by
+/// `Scope::set` and `eval_function`);
 /// thus, even at this lexical analysis stage, we know what is
 /// and isn't a special form.
 fn is_special(sexpr: &SExpr, scope: &Scope) -> Option<(Var, Option<String>)> {
@@ -90,7 +173,7 @@ fn is_special(sexpr: &SExpr, scope: &Scope) -> Option<(Var, Option<String>)> {
     };
     let expr = var.as_ref();
 
-    if let Expr::Special(..) = expr {
+    if expr.special_name().is_some() {
         // this is an "anonymous" (for our purposes) special form, stop here
         return Some((var.clone(), None));
     };
@@ -100,8 +183,8 @@ fn is_special(sexpr: &SExpr, scope: &Scope) -> Option<(Var, Option<String>)> {
     };
     if let Some(outer_val) = scope.lookup(s) {
         //
-        if let Expr::Special(..) = outer_val.as_ref() {
-            return Some((var.clone(), Some(s.clone())));
+        if outer_val.special_name().is_some() {
+            return Some((outer_val, Some(s.clone())));
         }
     }
     None
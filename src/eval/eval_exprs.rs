@@ -1,48 +1,71 @@
+use super::macros::expand_macro;
 use crate::ast::errors::{EResult, EvalError};
-use crate::ast::{Arity, CallForm, Expr, Function, OwnedSExpr, SExpr, Var};
+use crate::ast::{
+    Arity, CallForm, Expr, Function, OwnedSExpr, SExpr, Value, Var,
+};
+use crate::context::Context;
 use crate::scope::Scope;
 
 /// Evaluate an expression. Handles a few cases:
 /// 1) If it's an s-expression, it's evaluated (see eval_sexpr, below);
-/// 2) if it's a symbol, it's retrieved from the current scope;
+/// 2) if it's a symbol, it's retrieved from the current scope (or, if it's bound
+///    to a dynamic variable, that variable's current value);
 /// 3) all other expression types are returned unchanged.
 ///
 /// Note that `eval_sexpr` usually needs to evaluate its arguments,
 /// which means it will need to recursively call this function.
-pub fn eval(var: &Var, scope: &mut Scope) -> EResult<Var> {
+pub fn eval(var: &Var, scope: &mut Scope, ctx: &mut Context) -> EResult<Var> {
     match var.as_ref() {
-        Expr::SExpr(sexpr) => eval_sexpr(sexpr, scope),
-        Expr::Symbol(name) => scope.lookup_or_error(name),
+        Expr::SExpr(sexpr) => eval_sexpr(sexpr, scope, ctx),
+        Expr::Symbol(name) => {
+            let value = scope.lookup_or_error(name)?;
+            match value.as_ref() {
+                Expr::Dynamic(cell) => Ok(cell.get()),
+                _ => Ok(value),
+            }
+        },
         _ => Ok(var.clone()), // clones the Rc, not the value
     }
 }
 
 /// Evaluate an s-expression.
-/// Handles 4 cases based on evaluating the first element of the list:
+/// Handles 5 cases based on evaluating the first element of the list:
 /// 1) an empty s-expression is returned unchanged;
 /// 2) a special form is called with all arguments as-is (unevaluated), and
 ///    provided with a mutable reference to the scope;
-/// 3) a proc is evaluated by calling `eval_proc`, below;
-/// 4) everything else is a runtime error
-pub fn eval_sexpr(sexpr: &SExpr, scope: &mut Scope) -> EResult<Var> {
+/// 3) a macro call is expanded, and the expansion evaluated instead;
+/// 4) a proc is evaluated by calling `eval_proc`, below;
+/// 5) everything else is a runtime error
+pub fn eval_sexpr(
+    sexpr: &SExpr,
+    scope: &mut Scope,
+    ctx: &mut Context,
+) -> EResult<Var> {
     if sexpr.is_empty() {
         return Ok(Var::new(Expr::empty()));
     }
 
     // evaluate head
-    let head = eval(sexpr.first().unwrap(), scope)?;
+    let head = eval(sexpr.first().unwrap(), scope, ctx)?;
 
     // evaluate entire s-expression
     let tail = &sexpr[1..];
     match head.as_ref() {
-        Expr::Special(special) => (special.eval)(tail, scope),
+        Expr::Special(special) => {
+            check_arity(&special.arity, &special.name, tail.len())?;
+            (special.eval)(tail, scope, ctx)
+        },
+        Expr::Macro(mac) => {
+            let expansion = expand_macro(mac, tail, ctx)?;
+            eval(&expansion, scope, ctx)
+        },
         Expr::Function(func) => {
-            let eval_args: OwnedSExpr =
-                tail.iter()
-                    .map(|e| eval(e, scope))
-                    .collect::<Result<OwnedSExpr, EvalError>>()?;
+            let eval_args: OwnedSExpr = tail
+                .iter()
+                .map(|e| eval(e, scope, ctx))
+                .collect::<Result<OwnedSExpr, EvalError>>()?;
 
-            eval_function(func, eval_args)
+            eval_function(func, eval_args, ctx)
         },
         _ => {
             Err(EvalError::NotCallable(
@@ -52,38 +75,233 @@ pub fn eval_sexpr(sexpr: &SExpr, scope: &mut Scope) -> EResult<Var> {
     }
 }
 
+/// Evaluate a sequence of expressions in order, returning the value of the
+/// last one (or `nil` if there are none)
+pub fn eval_body(
+    body: &SExpr,
+    scope: &mut Scope,
+    ctx: &mut Context,
+) -> EResult<Var> {
+    let mut result = Var::new(Expr::Value(Value::Nil));
+    for var in body.iter() {
+        result = eval(var, scope, ctx)?;
+    }
+    Ok(result)
+}
+
 /// Evaluate a function call by first evaluating all arguments, then
 /// sending the array of evaluated arguments to the proc.
 ///
-/// Note that, unlike special forms, functions don't have any access to the scope.
+/// Note that, unlike special forms, functions don't have any access to the scope
+/// (but builtins do get the `Context`).
 /// Of course scope will be accessed while evaluating the arguments,
 /// including special forms trhat may potentially modify it.
-pub fn eval_function(func: &Function, eval_args: OwnedSExpr) -> EResult<Var> {
-    check_arity(&func.arity, &func.name, eval_args.len())?;
+///
+/// Special forms may not be passed to or returned from functions (see
+/// `Scope::set`).
+pub fn eval_function(
+    func: &Function,
+    eval_args: OwnedSExpr,
+    ctx: &mut Context,
+) -> EResult<Var> {
+    if let Some(special) = eval_args
+        .iter()
+        .find_map(|arg| arg.special_name())
+    {
+        return Err(EvalError::SpecialArgument {
+            function: func.name.clone(),
+            special: special.to_string(),
+        });
+    }
+
+    let result = call_function(func, eval_args, ctx)?;
+    if let Some(special) = result.special_name() {
+        return Err(EvalError::ReturnedSpecial {
+            function: func.name.clone(),
+            special: special.to_string(),
+        });
+    }
+    Ok(result)
+}
 
+fn call_function(
+    func: &Function,
+    eval_args: OwnedSExpr,
+    ctx: &mut Context,
+) -> EResult<Var> {
     match &func.form {
-        CallForm::Builtin(f) => f(&eval_args),
-        CallForm::Lambda { sexpr, scope } => {
-            let mut arg_scope = scope.bind_args(&func.arguments, &eval_args);
-            eval_sexpr(sexpr, &mut arg_scope)
+        CallForm::Builtin(f) => {
+            check_arity(&func.arity, &func.name, eval_args.len())?;
+            f(&eval_args, ctx)
+        },
+        CallForm::Native(f) => {
+            check_arity(&func.arity, &func.name, eval_args.len())?;
+            f(ctx, &eval_args)
+        },
+        CallForm::Lambda { body, scope, group } => {
+            let (positional, keywords) = split_keyword_args(func, &eval_args)?;
+            check_arity(&func.arity, &func.name, positional.len())?;
+
+            // parameters shadow the group's names
+            let captured = match group {
+                Some(group) => {
+                    let mut group_scope = scope.local_child();
+                    group.bind(&mut group_scope)?;
+                    group_scope
+                },
+                None => scope.clone(),
+            };
+            let mut arg_scope =
+                bind_args(func, &captured, positional, &keywords, ctx)?;
+            ctx.enter_function(&func.name);
+            let result = eval_body(body, &mut arg_scope, ctx);
+            ctx.leave_function();
+            result
+        },
+        CallForm::Overloaded(clauses) => {
+            let clause = clauses
+                .iter()
+                .find(|clause| accepts_args(clause, &eval_args))
+                .ok_or_else(|| {
+                    EvalError::Arity {
+                        name: func.name.clone(),
+                        arity: func.arity.clone(),
+                        num_args_provided: eval_args.len(),
+                    }
+                })?;
+            call_function(clause, eval_args, ctx)
+        },
+        CallForm::Curry {
+            inner,
+            bound,
+            keywords,
+            auto,
+        } => {
+            let (positional, _) = split_keyword_args(func, &eval_args)?;
+            let n_args = positional.len();
+            if *auto && n_args < func.arity.min() {
+                return Ok(func.partial(&eval_args)?.into());
+            }
+            check_arity(&func.arity, &func.name, n_args)?;
+
+            // `bound... positional... keywords... caller's keywords...`
+            let mut args = bound.to_vec();
+            args.extend_from_slice(positional);
+            args.extend(keywords.iter().cloned());
+            args.extend_from_slice(&eval_args[n_args..]);
+            call_function(inner, args, ctx)
         },
     }
 }
 
-fn check_arity(
+/// Whether a call with these arguments would pass the arity check
+fn accepts_args(func: &Function, args: &SExpr) -> bool {
+    split_keyword_args(func, args)
+        .is_ok_and(|(positional, _)| func.arity.accepts(positional.len()))
+}
+
+pub(super) fn check_arity(
     arity: &Arity,
     name: &str,
     n_args: usize,
 ) -> Result<(), EvalError> {
-    if let &Arity::Fixed(arity) = arity {
-        if n_args != arity {
-            return Err(EvalError::Arity {
-                name: name.to_string(),
-                arity,
-                num_args_provided: n_args,
-            });
-        }
-    };
-
-    Ok(())
+    if arity.accepts(n_args) {
+        Ok(())
+    } else {
+        Err(EvalError::Arity {
+            name: name.to_string(),
+            arity: arity.clone(),
+            num_args_provided: n_args,
+        })
+    }
+}
+
+/// Split a call's arguments into positional and `:label value` keyword
+/// arguments (see `Parameters::keyword_args_start`)
+fn split_keyword_args<'a>(
+    func: &Function,
+    args: &'a SExpr,
+) -> EResult<(&'a SExpr, Vec<(&'a str, &'a Var)>)> {
+    let params = &func.params;
+    let (positional, keyword_args) =
+        args.split_at(params.keyword_args_start(args));
+    let keywords = keyword_args
+        .chunks(2)
+        .map(|pair| {
+            let label = pair[0].expect_keyword()?;
+            if params.keyword_param(label).is_none() {
+                return Err(EvalError::UnknownKeyword {
+                    name: func.name.clone(),
+                    keyword: label.to_string(),
+                });
+            }
+            let value = pair.get(1).ok_or_else(|| {
+                EvalError::Syntax {
+                    expected: format!("a value after :{label}"),
+                    actual: "end of arguments".to_string(),
+                }
+            })?;
+            Ok((label, value))
+        })
+        .collect::<EResult<Vec<_>>>()?;
+
+    Ok((positional, keywords))
+}
+
+/// Create the scope for a call to a lambda: a child of its captured scope,
+/// with all of its parameters bound.
+///
+/// Parameters are bound in order, and defaults are evaluated in the new
+/// scope, so they can refer to earlier parameters.
+/// Expects that the arity has already been checked.
+fn bind_args(
+    func: &Function,
+    captured: &Scope,
+    positional: &SExpr,
+    keywords: &[(&str, &Var)],
+    ctx: &mut Context,
+) -> EResult<Scope> {
+    let params = &func.params;
+    let mut arg_scope = captured.local_child();
+    let mut args = positional.iter();
+
+    for pattern in params.positional.iter() {
+        pattern.bind(args.next().unwrap(), &mut arg_scope)?;
+    }
+
+    for param in params.optional.iter() {
+        let value = match (args.next(), &param.default) {
+            (Some(arg), _) => arg.clone(),
+            (None, Some(default)) => eval(default, &mut arg_scope, ctx)?,
+            (None, None) => Value::Nil.into(),
+        };
+        arg_scope.set(&param.name, value)?;
+    }
+
+    if let Some(rest) = &params.rest {
+        arg_scope.set(
+            rest,
+            Expr::SExpr(args.cloned().collect()).into(),
+        )?;
+    }
+
+    for param in params.keyword.iter() {
+        let provided = keywords
+            .iter()
+            .rev() // last one wins
+            .find(|(label, _)| *label == param.label);
+        let value = match (provided, &param.default) {
+            (Some((_, arg)), _) => (*arg).clone(),
+            (None, Some(default)) => eval(default, &mut arg_scope, ctx)?,
+            (None, None) => {
+                return Err(EvalError::MissingKeyword {
+                    name: func.name.clone(),
+                    keyword: param.label.clone(),
+                });
+            },
+        };
+        arg_scope.set(&param.name, value)?;
+    }
+
+    Ok(arg_scope)
 }
@@ -0,0 +1,50 @@
+use super::eval_function;
+use crate::ast::{Expr, Macro, MacroExpander, SExpr, Var};
+use crate::{Context, EResult, Scope};
+
+/// Expand a call to `mac`, given the unevaluated argument forms, into the
+/// form to evaluate instead
+pub fn expand_macro(
+    mac: &Macro,
+    args: &SExpr,
+    ctx: &mut Context,
+) -> EResult<Var> {
+    match &mac.expander {
+        MacroExpander::Procedure(expander) => {
+            eval_function(expander, args.to_vec(), ctx)
+        },
+        MacroExpander::Rules(rules) => rules.expand(args, ctx),
+    }
+}
+
+/// If `form` is a call to a macro, expand it (once)
+pub fn macroexpand_1(
+    form: &Var,
+    scope: &Scope,
+    ctx: &mut Context,
+) -> EResult<Option<Var>> {
+    let Expr::SExpr(sexpr) = form.as_ref() else {
+        return Ok(None);
+    };
+    let Some(head) = sexpr.first() else {
+        return Ok(None);
+    };
+    let head = match head.as_ref() {
+        Expr::Symbol(name) => scope.lookup(name),
+        _ => Some(head.clone()),
+    };
+    match head.as_ref().map(|var| var.as_ref()) {
+        Some(Expr::Macro(mac)) => expand_macro(mac, &sexpr[1..], ctx).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Expand `form` until it's no longer a macro call. Only the form itself is
+/// expanded, not its subforms.
+pub fn macroexpand(form: &Var, scope: &Scope, ctx: &mut Context) -> EResult<Var> {
+    let mut form = form.clone();
+    while let Some(expansion) = macroexpand_1(&form, scope, ctx)? {
+        form = expansion;
+    }
+    Ok(form)
+}
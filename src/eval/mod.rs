@@ -1,5 +1,7 @@
 mod closures;
 mod eval_exprs;
+mod macros;
 
 pub use closures::*;
 pub use eval_exprs::*;
+pub use macros::*;
@@ -0,0 +1,108 @@
+use std::any::Any;
+use std::path::Path;
+
+use anyhow::{Context as _, Result};
+
+use crate::ast::{Expr, Function, Origin, OwnedSExpr, Value, Var};
+use crate::parser::parse_program;
+use crate::{Context, EResult, EvalError, Scope, eval, eval_function};
+
+/// A self-contained interpreter, for embedding: a global scope on top of the
+/// builtins, and a `Context` (output, counters...) of its own.
+///
+/// Interpreters don't share any state, so any number of them can be used
+/// side by side.
+pub struct Interpreter {
+    scope: Scope,
+    ctx: Context,
+}
+
+impl Interpreter {
+    /// An interpreter that prints to stdout
+    pub fn new() -> Self {
+        Self::with_context(Context::new())
+    }
+
+    /// An interpreter with a custom context (e.g. `Context::buffered()`)
+    pub fn with_context(ctx: Context) -> Self {
+        Interpreter {
+            scope: ctx.root().child(),
+            ctx,
+        }
+    }
+
+    pub fn context(&mut self) -> &mut Context {
+        &mut self.ctx
+    }
+
+    /// Evaluate a parsed expression in the global scope
+    pub fn eval(&mut self, var: &Var) -> EResult<Var> {
+        eval(var, &mut self.scope, &mut self.ctx)
+    }
+
+    /// Evaluate all the top-level expressions in `text` (s-expressions or
+    /// atoms, e.g. `42`), in order, returning the value of the last one (or
+    /// `nil` if there are none)
+    pub fn eval_str(&mut self, text: &str) -> Result<Var> {
+        self.eval_source(text, "<string>")
+    }
+
+    /// Evaluate the file at `path` (see `eval_str`)
+    pub fn eval_file(&mut self, path: impl AsRef<Path>) -> Result<Var> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read {}", path.display()))?;
+        self.eval_source(&text, &path.display().to_string())
+    }
+
+    /// `text` comes from `file`: functions it defines record where
+    fn eval_source(&mut self, text: &str, file: &str) -> Result<Var> {
+        let mut result = Var::new(Expr::Value(Value::Nil));
+        for (line, expr) in parse_program(text)? {
+            self.ctx.set_location(Some(Origin {
+                file: file.to_string(),
+                line,
+            }));
+            let value = self.eval(&expr);
+            self.ctx.set_location(None);
+            result = value?;
+        }
+        Ok(result)
+    }
+
+    /// Bind `name` to `value` in the global scope, like `define` would
+    pub fn define(&mut self, name: &str, value: impl Into<Var>) -> EResult<()> {
+        self.scope.set(name, value.into())
+    }
+
+    /// See `Context::register_method`
+    pub fn register_method<T: Any>(&mut self, name: &str, method: Function) {
+        self.ctx
+            .register_method::<T>(name, method)
+    }
+
+    /// The value bound to `name` in the global scope
+    pub fn lookup(&self, name: &str) -> Option<Var> {
+        self.scope.lookup(name)
+    }
+
+    /// Call the function bound to `name` with the (already evaluated)
+    /// arguments `args`
+    pub fn call(&mut self, name: &str, args: OwnedSExpr) -> EResult<Var> {
+        let func = self.eval(&Expr::Symbol(name.to_string()).into())?;
+        match func.as_ref() {
+            Expr::Function(func) => eval_function(func, args, &mut self.ctx),
+            _ => {
+                Err(EvalError::NotCallable(
+                    func.type_str().to_string(),
+                ))
+            },
+        }
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,6 +1,8 @@
 pub mod ast;
 mod builtins;
+mod context;
 mod eval;
+mod interpreter;
 pub mod parser;
 pub mod repl;
 mod scope;
@@ -8,5 +10,7 @@ mod scope;
 
 pub use ast::errors::*;
 pub use builtins::*;
+pub use context::*;
 pub use eval::*;
+pub use interpreter::*;
 pub use scope::*;
@@ -0,0 +1,392 @@
+use std::fmt::{Display, Formatter};
+
+use anyhow::{Result, anyhow, bail};
+use itertools::Itertools;
+
+use super::expr_builder::try_negate;
+use super::token_handlers::parse_token;
+use super::tokenizer::{Token, tokenize};
+use crate::ast::{Expr, Mapping, OwnedSExpr, Var};
+
+/// An arena holding every node of a parsed program.
+///
+/// The default parser allocates a separate `Rc<Expr>` for every node, which
+/// is slow to build and drop (and fragments memory) for large data files.
+/// Here, the whole tree lives in two flat vectors, and nodes refer to each
+/// other by index.
+///
+/// Arena nodes are read-only. Use [`ArenaVar::to_var`] to materialize a
+/// subtree as regular `Var`s when it needs to be evaluated.
+#[derive(Debug, Default)]
+pub struct AstArena {
+    nodes: Vec<ArenaNode>,
+    /// the children of each list (or keys and values of each record),
+    /// stored contiguously
+    children: Vec<NodeId>,
+}
+
+/// Index of a node in an [`AstArena`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(u32);
+
+// Atoms are most of the nodes: boxing them would bring back the
+// per-node allocations the arena is here to avoid
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+enum ArenaNode {
+    Atom(Expr),
+    SExpr {
+        start: u32,
+        len: u32,
+    },
+    /// `len` alternating keyword and value nodes
+    Record {
+        start: u32,
+        len: u32,
+    },
+}
+
+impl AstArena {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of nodes in the arena
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Get a handle to a node.
+    /// Panics if the id came from a different arena.
+    pub fn get(&self, id: NodeId) -> ArenaVar<'_> {
+        assert!(
+            (id.0 as usize) < self.nodes.len(),
+            "{id:?} not in arena"
+        );
+        ArenaVar { arena: self, id }
+    }
+
+    /// Parse text into the arena, returning the id of its root s-expression
+    pub fn parse_text(&mut self, s: &str) -> Result<NodeId> {
+        let tokens = tokenize(s);
+        self.parse_tokens(&mut tokens.iter())
+    }
+
+    /// Arena version of `expr_builder::parse_tokens`.
+    ///
+    /// If parsing fails, the arena is rolled back to its previous state.
+    pub fn parse_tokens<'a>(
+        &mut self,
+        token_iter: &mut impl Iterator<Item = &'a Token>,
+    ) -> Result<NodeId> {
+        let (n_nodes, n_children) = (self.nodes.len(), self.children.len());
+
+        let root = self.build_sexpr(token_iter);
+        if root.is_err() {
+            self.nodes.truncate(n_nodes);
+            self.children.truncate(n_children);
+        }
+        root
+    }
+
+    /// Unlike `expr_builder::build_sexpr`, this doesn't recurse - since
+    /// nodes are just indices, keeping an explicit stack is easy here.
+    fn build_sexpr<'a>(
+        &mut self,
+        token_iter: &mut impl Iterator<Item = &'a Token>,
+    ) -> Result<NodeId> {
+        let first_token = token_iter
+            .next()
+            .ok_or(anyhow!("No tokens"))?;
+
+        let Some(closer) = first_token.closer() else {
+            bail!("Expression should begin with '(', but got {first_token:#?}")
+        };
+
+        // `pending` holds the children of every still-open s-expression;
+        // `frames` holds the index in `pending` where each one starts, and
+        // the token that will close it.
+        let mut pending: Vec<NodeId> = Vec::new();
+        let mut frames: Vec<(usize, Token)> = vec![(0, closer)];
+
+        while let Some((frame_start, closer)) = frames.last() {
+            let frame_start = *frame_start;
+            let token = token_iter.next().ok_or(anyhow!(
+                "Token stream ended before S-Expression was complete"
+            ))?;
+
+            let id = match token {
+                Token::ParenStart | Token::BracketStart => {
+                    frames.push((pending.len(), token.closer().unwrap()));
+                    continue;
+                },
+                Token::BraceStart => {
+                    frames.push((pending.len(), Token::BraceEnd));
+                    continue;
+                },
+                Token::ParenEnd | Token::BracketEnd | Token::BraceEnd => {
+                    if token != closer {
+                        bail!(
+                            "Expected {closer:?} to close S-expression, got \
+                             {token:?}"
+                        )
+                    }
+                    frames.pop();
+                    let items = &pending[frame_start..];
+                    if *token == Token::BraceEnd {
+                        self.check_record(items)?;
+                    }
+
+                    let start = self.children.len();
+                    self.children.extend_from_slice(items);
+                    pending.truncate(frame_start);
+                    let (start, len) = (
+                        to_index(start)?,
+                        to_index(self.children.len() - start)?,
+                    );
+                    self.push_node(if *token == Token::BraceEnd {
+                        ArenaNode::Record { start, len }
+                    } else {
+                        ArenaNode::SExpr { start, len }
+                    })?
+                },
+                Token::Dash => {
+                    let next_expr = token_iter
+                        .next()
+                        .ok_or(anyhow!(
+                            "Token stream ended after negative sign"
+                        ))
+                        .and_then(parse_token)
+                        .and_then(try_negate)?;
+                    self.push_node(ArenaNode::Atom(next_expr))?
+                },
+                token => self.push_node(ArenaNode::Atom(parse_token(token)?))?,
+            };
+            pending.push(id);
+        }
+
+        if let Some(token) = token_iter.next() {
+            bail!(
+                "S-expression is complete, but tokens remain ({token:#?}). \
+                 Unmatched closing parentheses?"
+            )
+        }
+
+        // the root is the only thing left
+        Ok(pending.pop().unwrap())
+    }
+
+    /// Same rules as `expr_builder::build_record`
+    fn check_record(&self, items: &[NodeId]) -> Result<()> {
+        if !items.len().is_multiple_of(2) {
+            bail!("Record literal has a key without a value");
+        }
+        for key in items.iter().step_by(2) {
+            self.get(*key)
+                .atom()
+                .ok_or(anyhow!("Record keys must be keywords"))?
+                .expect_keyword()?;
+        }
+        Ok(())
+    }
+
+    fn push_node(&mut self, node: ArenaNode) -> Result<NodeId> {
+        let id = NodeId(to_index(self.nodes.len())?);
+        self.nodes.push(node);
+        Ok(id)
+    }
+}
+
+fn to_index(n: usize) -> Result<u32> {
+    u32::try_from(n).map_err(|_| anyhow!("Too many nodes for arena"))
+}
+
+/// Turn text into an s-expression in a new arena
+pub fn parse_text_arena(s: &str) -> Result<(AstArena, NodeId)> {
+    let mut arena = AstArena::new();
+    let root = arena.parse_text(s)?;
+    Ok((arena, root))
+}
+
+/****************\
+|* Node handles *|
+\****************/
+
+/// A cheap (`Copy`) handle to a node in an [`AstArena`].
+/// This is the arena equivalent of a `Var`.
+#[derive(Debug, Clone, Copy)]
+pub struct ArenaVar<'a> {
+    arena: &'a AstArena,
+    id: NodeId,
+}
+
+impl<'a> ArenaVar<'a> {
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// The node's expression, unless it's an s-expression or record
+    pub fn atom(&self) -> Option<&'a Expr> {
+        match self.node() {
+            ArenaNode::Atom(expr) => Some(expr),
+            _ => None,
+        }
+    }
+
+    /// The node's children, if it's an s-expression
+    pub fn items(&self) -> Option<impl ExactSizeIterator<Item = ArenaVar<'a>>> {
+        match *self.node() {
+            ArenaNode::SExpr { start, len } => Some(self.children(start, len)),
+            _ => None,
+        }
+    }
+
+    /// The node's keys and values, if it's a record
+    pub fn entries(
+        &self,
+    ) -> Option<impl Iterator<Item = (&'a str, ArenaVar<'a>)>> {
+        match *self.node() {
+            ArenaNode::Record { start, len } => {
+                Some(
+                    self.children(start, len)
+                        .tuples()
+                        .map(|(k, v)| {
+                            // keys are checked while parsing
+                            (
+                                k.atom()
+                                    .unwrap()
+                                    .expect_keyword()
+                                    .unwrap(),
+                                v,
+                            )
+                        }),
+                )
+            },
+            _ => None,
+        }
+    }
+
+    /// Copy this subtree out of the arena as regular (`Rc`-based) `Var`s
+    pub fn to_var(&self) -> Var {
+        if let Some(items) = self.items() {
+            Var::new(Expr::SExpr(
+                items
+                    .map(|item| item.to_var())
+                    .collect::<OwnedSExpr>(),
+            ))
+        } else if let Some(entries) = self.entries() {
+            Var::new(Expr::Record(
+                entries
+                    .map(|(k, v)| (k.to_string(), v.to_var()))
+                    .collect::<Mapping>(),
+            ))
+        } else {
+            Var::new(clone_atom(self.atom().unwrap()))
+        }
+    }
+
+    fn children(
+        &self,
+        start: u32,
+        len: u32,
+    ) -> impl ExactSizeIterator<Item = ArenaVar<'a>> + use<'a> {
+        let arena = self.arena;
+        let (start, len) = (start as usize, len as usize);
+        arena.children[start..start + len]
+            .iter()
+            .map(move |&id| ArenaVar { arena, id })
+    }
+
+    fn node(&self) -> &'a ArenaNode {
+        &self.arena.nodes[self.id.0 as usize]
+    }
+}
+
+/// The parser only produces leaf expressions that are cheap to clone
+fn clone_atom(expr: &Expr) -> Expr {
+    match expr {
+        Expr::Symbol(s) => Expr::Symbol(s.clone()),
+        Expr::Keyword(s) => Expr::Keyword(s.clone()),
+        Expr::Value(v) => Expr::Value(v.clone()),
+        other => unreachable!("parser produced non-atom {other}"),
+    }
+}
+
+impl Display for ArenaVar<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.items(), self.atom()) {
+            (Some(items), _) => write!(f, "( {} )", items.format(" ")),
+            (_, Some(atom)) => atom.fmt(f),
+            // records are rare enough not to bother
+            _ => self.to_var().fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_text;
+
+    fn assert_same_as_rc_parse(input: &str) {
+        let expected = Var::new(Expr::SExpr(parse_text(input).unwrap()));
+        let (arena, root) = parse_text_arena(input).unwrap();
+        let actual = arena.get(root);
+
+        assert_eq!(actual.to_var(), expected);
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_matches_rc_parse() {
+        assert_same_as_rc_parse("()");
+        assert_same_as_rc_parse("(1 2.5 'three' four :five)");
+        assert_same_as_rc_parse("(a (b (c - 1) ()) (d -2.5) e)");
+        assert_same_as_rc_parse("[a (b [c]) []]");
+        assert_same_as_rc_parse("(a {:b (c {:d 1})} {})");
+    }
+
+    #[test]
+    fn test_handles() {
+        let (arena, root) = parse_text_arena("(a (b c) 3)").unwrap();
+        let items: Vec<ArenaVar> = arena
+            .get(root)
+            .items()
+            .unwrap()
+            .collect();
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            items[0].atom(),
+            Some(&Expr::Symbol("a".to_string()))
+        );
+        assert!(items[1].atom().is_none());
+        assert_eq!(items[1].items().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_rollback_on_error() {
+        let mut arena = AstArena::new();
+        let first = arena.parse_text("(a b)").unwrap();
+        let n_nodes = arena.len();
+
+        assert!(arena.parse_text("(c (d e)").is_err());
+        assert!(arena.parse_text("(c d))").is_err());
+        assert!(arena.parse_text("(c [d e)]").is_err());
+        assert!(
+            arena
+                .parse_text("(c {:d e :f})")
+                .is_err()
+        );
+        assert!(arena.parse_text("(c {d e})").is_err());
+        assert_eq!(arena.len(), n_nodes);
+        assert_eq!(
+            arena.get(first).to_string(),
+            "( #Symbol[a] #Symbol[b] )"
+        );
+    }
+}
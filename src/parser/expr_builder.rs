@@ -1,8 +1,8 @@
 use anyhow::{Result, anyhow, bail};
 
 use super::token_handlers::parse_token;
-use super::tokenizer::{Token, tokenize};
-use crate::ast::{Expr, OwnedSExpr, Value, Var};
+use super::tokenizer::{Token, tokenize, tokenize_with_lines};
+use crate::ast::{Expr, Mapping, OwnedSExpr, Value, Var};
 
 /// turn text into an s-expression
 pub fn parse_text(s: &str) -> Result<OwnedSExpr> {
@@ -10,6 +10,31 @@ pub fn parse_text(s: &str) -> Result<OwnedSExpr> {
     parse_tokens(&mut tokens.iter())
 }
 
+/// turn text with any number of top-level expressions (e.g. a file) into
+/// expressions, each with the line it starts on. Unlike `parse_text`, a
+/// top-level expression can be an atom (e.g. `42` or `x`).
+pub fn parse_program(s: &str) -> Result<Vec<(usize, Var)>> {
+    let tokens = tokenize_with_lines(s);
+    let mut token_iter = tokens.iter();
+    let mut exprs = Vec::new();
+    while let Some((line, token)) = token_iter.next() {
+        if matches!(
+            token,
+            Token::ParenEnd | Token::BracketEnd | Token::BraceEnd
+        ) {
+            bail!("Unmatched closing {token:?}")
+        }
+        let mut rest = token_iter
+            .by_ref()
+            .map(|(line, token)| (Some(*line), token));
+        exprs.push((
+            *line,
+            build_node(Some(*line), token, &mut rest)?,
+        ));
+    }
+    Ok(exprs)
+}
+
 /// Turn a stream of tokens into an S-expression
 ///
 /// WARNING: This function recurses! By default limited to a stack of 128.
@@ -29,12 +54,17 @@ pub fn parse_tokens<'a>(
         .next()
         .ok_or(anyhow!("No tokens"))?;
 
-    let Token::ParenStart = first_token else {
+    let Some(closer) = first_token.closer() else {
         bail!("Expression should begin with '(', but got {first_token:#?}")
     };
 
     /* ** Build the root S-expression ** */
-    let root = build_sexpr(token_iter);
+    let root = build_sexpr(
+        &mut token_iter
+            .by_ref()
+            .map(|token| (None, token)),
+        &closer,
+    );
 
     // ensure tokens were exhausted
     // Surely there's a nicer way to write this?
@@ -50,49 +80,88 @@ pub fn parse_tokens<'a>(
     root
 }
 
-/// Build the s-expression from tokens
+/// Build the s-expression from tokens (with the line each starts on, if
+/// known), up to the `closer` token
 /// Will build nested s-expressions via recursion
 fn build_sexpr<'a>(
-    token_iter: &mut impl Iterator<Item = &'a Token>,
+    token_iter: &mut impl Iterator<Item = (Option<usize>, &'a Token)>,
+    closer: &Token,
 ) -> Result<OwnedSExpr> {
     let mut sexpr = OwnedSExpr::new();
 
     loop {
-        let token = token_iter.next().ok_or(anyhow!(
+        let (line, token) = token_iter.next().ok_or(anyhow!(
             "Token stream ended before S-Expression was complete"
         ))?;
 
         // add to the current s-expression as indicated via the token
         match token {
-            Token::ParenEnd => {
+            Token::ParenEnd | Token::BracketEnd | Token::BraceEnd => {
+                if token != closer {
+                    bail!(
+                        "Expected {closer:?} to close S-expression, got \
+                         {token:?}"
+                    )
+                }
                 break;
             },
-            Token::ParenStart => {
-                let sub_expr = build_sexpr(token_iter)?;
-                sexpr.push(Var::new(Expr::SExpr(sub_expr)));
-            },
-            Token::Dash => {
-                let next_expr = token_iter
-                    .next()
-                    .ok_or(anyhow!(
-                        "Token stream ended after negative sign"
-                    ))
-                    .and_then(parse_token)
-                    .and_then(try_negate)?;
-                sexpr.push(Var::new(next_expr));
-            },
-
-            token => {
-                let next_expr = parse_token(token)?;
-                sexpr.push(Var::new(next_expr));
-            },
+            token => sexpr.push(build_node(line, token, token_iter)?),
         }
     }
 
     Ok(sexpr)
 }
 
-fn try_negate(expr: Expr) -> Result<Expr> {
+/// Build the expression starting with `token` (on `line`, if known), taking
+/// the rest of it (e.g. a nested s-expression) from `token_iter`
+fn build_node<'a>(
+    line: Option<usize>,
+    token: &Token,
+    token_iter: &mut impl Iterator<Item = (Option<usize>, &'a Token)>,
+) -> Result<Var> {
+    let expr = match token {
+        Token::ParenStart | Token::BracketStart => {
+            let sub_closer = token.closer().unwrap();
+            Expr::SExpr(build_sexpr(token_iter, &sub_closer)?)
+        },
+        Token::BraceStart => {
+            let items = build_sexpr(token_iter, &Token::BraceEnd)?;
+            build_record(items)?
+        },
+        Token::Dash => {
+            token_iter
+                .next()
+                .ok_or(anyhow!(
+                    "Token stream ended after negative sign"
+                ))
+                .and_then(|(_, token)| parse_token(token))
+                .and_then(try_negate)?
+        },
+        token => parse_token(token)?,
+    };
+    Ok(match line {
+        Some(line) => Var::at_line(expr, line),
+        None => Var::new(expr),
+    })
+}
+
+/// Record literals are written `{:key1 value1 :key2 value2 ...}`.
+/// Like `quote`, the values are _not_ evaluated.
+pub(super) fn build_record(items: OwnedSExpr) -> Result<Expr> {
+    if !items.len().is_multiple_of(2) {
+        bail!("Record literal has a key without a value");
+    }
+    items
+        .chunks(2)
+        .map(|pair| {
+            let key = pair[0].expect_keyword()?;
+            Ok((key.to_string(), pair[1].clone()))
+        })
+        .collect::<Result<Mapping>>()
+        .map(Expr::Record)
+}
+
+pub(super) fn try_negate(expr: Expr) -> Result<Expr> {
     match expr {
         Expr::Value(Value::Int(n)) => Ok(Value::Int(-n).into()),
         Expr::Value(Value::Float(f)) => Ok(Value::Float(-f).into()),
@@ -115,6 +184,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_brackets() {
+        assert_eq!(
+            parse_text("[a (b [c])]").unwrap(),
+            parse_text("(a (b (c)))").unwrap()
+        );
+        assert!(parse_text("(a [b)]").is_err());
+        assert!(parse_text("[a)").is_err());
+    }
+
+    #[test]
+    fn test_parse_lambda_list_markers() {
+        let result = parse_text("(a &opt b & c &key d)").unwrap();
+        assert_eq!(result.len(), 7);
+        assert_eq!(
+            *result[1].as_ref(),
+            Expr::Symbol("&opt".to_string())
+        );
+
+        assert!(parse_text("(a&b)").is_err());
+        assert!(parse_text("(&rest)").is_err());
+        assert!(parse_text("(:&)").is_err());
+    }
+
+    #[test]
+    fn test_parse_records() {
+        let result = parse_text("({:a 1 :b (c d)})").unwrap();
+        let Expr::Record(record) = result.first().unwrap().as_ref() else {
+            panic!("Not a record: {result:?}")
+        };
+        assert_eq!(record.len(), 2);
+        assert_eq!(
+            *record["a"].as_ref(),
+            Expr::Value(Value::Int(1))
+        );
+
+        assert!(parse_text("({:a})").is_err());
+        assert!(parse_text("({a 1})").is_err());
+        assert!(parse_text("{:a 1}").is_err());
+    }
+
     #[test]
     fn test_parse_ints() {
         do_literal_test("0", Value::Int(0));
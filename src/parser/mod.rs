@@ -1,5 +1,7 @@
+mod arena;
 mod expr_builder;
 mod token_handlers;
 mod tokenizer;
 
+pub use arena::*;
 pub use expr_builder::*;
@@ -1,4 +1,4 @@
-use anyhow::{anyhow, Result};
+use anyhow::{Result, anyhow};
 use lazy_static::lazy_static;
 use litrs::Literal;
 use regex::Regex;
@@ -57,10 +57,16 @@ fn parse_literal(s: &str) -> Result<Value> {
 /// Or `nil`, which is probably incorrectly treated as a literal?
 /// Must only be called after ensuring that the word is not a literal.
 fn parse_identifier(s: &str) -> Result<Expr> {
-    if SYMBOL_RE.is_match(s) {
-        if s.starts_with(':') {
+    if s == "..." {
+        // the ellipsis in `syntax-rules` patterns and templates
+        Ok(Expr::Symbol(s.to_string()))
+    } else if LAMBDA_LIST_MARKERS.contains(&s) {
+        // `&` is only allowed on its own, to mark lambda list sections
+        Ok(Expr::Symbol(s.to_string()))
+    } else if SYMBOL_RE.is_match(s) {
+        if let Some(keyword) = s.strip_prefix(':') {
             // it's a keyword
-            Ok(Expr::Keyword(s.to_string()))
+            Ok(Expr::Keyword(keyword.to_string()))
         } else if s.to_lowercase() == "nil" {
             // it's probably nil
             if s == "nil" {
@@ -120,6 +126,9 @@ lazy_static! {
         Regex::new(r"^:?[a-zA-Z*+!\-_?][a-zA-Z0-9*+!\-_?]*$").unwrap();
 }
 
+/// Rest, optional and keyword sections of a lambda list
+const LAMBDA_LIST_MARKERS: [&str; 3] = ["&", "&opt", "&key"];
+
 /// Ensure literal doesn't have a suffix
 /// E.g., "15" is ok, "15u32" is not, because u32 is a rust thing)
 fn check_suffix(lit: Literal<&str>) -> Result<Literal<&str>> {
@@ -10,22 +10,57 @@ pub struct Quote {
     pub(super) content: String,
 }
 
+/// Note that square brackets are interchangeable with parentheses (as in
+/// Racket), but each opening bracket must be closed by the same kind.
+/// Braces delimit record literals.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     ParenStart,
     ParenEnd,
+    BracketStart,
+    BracketEnd,
+    BraceStart,
+    BraceEnd,
     Dash,
     Word(String),
     StringLit(Quote),
 }
 
+impl Token {
+    /// If this opens an s-expression, the token that closes it
+    pub fn closer(&self) -> Option<Token> {
+        match self {
+            ParenStart => Some(ParenEnd),
+            BracketStart => Some(BracketEnd),
+            _ => None,
+        }
+    }
+}
+
 pub fn tokenize(s: &str) -> Vec<Token> {
-    let mut tokens: Vec<Token> = vec![];
+    tokenize_with_lines(s)
+        .into_iter()
+        .map(|(_, token)| token)
+        .collect()
+}
+
+/// Like `tokenize`, with the (1-based) line each token starts on
+pub fn tokenize_with_lines(s: &str) -> Vec<(usize, Token)> {
+    let mut tokens: Vec<(usize, Token)> = vec![];
+    let mut next_line = 1;
+    // where the current word or string literal (with its sigil) starts
+    let mut start_line = 1;
     let mut current_word: String = "".to_string();
     let mut current_quote: Option<Quote> = None;
     let mut is_escaped: bool = false;
 
     for char in s.chars() {
+        // a newline is on the line it ends
+        let line = next_line;
+        if char == '\n' {
+            next_line += 1;
+        }
+
         /**********************************\
         |* Quoted string literal handling *|
         \**********************************/
@@ -36,7 +71,7 @@ pub fn tokenize(s: &str) -> Vec<Token> {
                     quote.content.push(char);
                     is_escaped = false;
                 } else {
-                    tokens.push(StringLit(quote));
+                    tokens.push((start_line, StringLit(quote)));
                     continue;
                 }
             } else if is_escaped {
@@ -55,6 +90,9 @@ pub fn tokenize(s: &str) -> Vec<Token> {
         |* Quote start *|
         \***************/
         else if char == '\'' || char == '"' {
+            if current_word.is_empty() {
+                start_line = line;
+            }
             current_quote = Some(Quote {
                 sigil: current_word.clone(),
                 mark: char,
@@ -65,32 +103,43 @@ pub fn tokenize(s: &str) -> Vec<Token> {
         /**************************************\
         |* Everything besides string literals *|
         \**************************************/
-        else if char == '(' || char == ')' || char.is_whitespace() {
-            push_word(&mut tokens, &mut current_word);
-            if char == '(' {
-                tokens.push(ParenStart)
-            } else if char == ')' {
-                tokens.push(ParenEnd)
+        else if "()[]{}".contains(char) || char.is_whitespace() {
+            push_word(&mut tokens, &mut current_word, start_line);
+            match char {
+                '(' => tokens.push((line, ParenStart)),
+                ')' => tokens.push((line, ParenEnd)),
+                '[' => tokens.push((line, BracketStart)),
+                ']' => tokens.push((line, BracketEnd)),
+                '{' => tokens.push((line, BraceStart)),
+                '}' => tokens.push((line, BraceEnd)),
+                _ => {},
             };
         }
         // leading dashes become the "dash" token
         else if current_word.is_empty() && char == '-' {
-            tokens.push(Dash)
+            tokens.push((line, Dash))
 
         // continue with current identifier
         } else {
+            if current_word.is_empty() {
+                start_line = line;
+            }
             current_word.push(char);
         }
     }
-    push_word(&mut tokens, &mut current_word);
+    push_word(&mut tokens, &mut current_word, start_line);
 
     tokens
 }
 
 #[inline]
-fn push_word(tokens: &mut Vec<Token>, current_word: &mut String) {
+fn push_word(
+    tokens: &mut Vec<(usize, Token)>,
+    current_word: &mut String,
+    line: usize,
+) {
     if !current_word.is_empty() {
-        tokens.push(Word(current_word.clone()));
+        tokens.push((line, Word(current_word.clone())));
         current_word.clear();
     }
 }
@@ -127,6 +176,43 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_brackets() {
+        assert_eq!(
+            tokenize("([x]y)"),
+            vec![
+                ParenStart,
+                BracketStart,
+                Word("x".to_string()),
+                BracketEnd,
+                Word("y".to_string()),
+                ParenEnd
+            ]
+        )
+    }
+
+    #[test]
+    fn test_token_lines() {
+        assert_eq!(
+            tokenize_with_lines("(a\nb 'c\nd')\ne"),
+            vec![
+                (1, ParenStart),
+                (1, Word("a".to_string())),
+                (2, Word("b".to_string())),
+                (
+                    2,
+                    StringLit(Quote {
+                        sigil: String::new(),
+                        mark: '\'',
+                        content: "c\nd".to_string()
+                    })
+                ),
+                (3, ParenEnd),
+                (4, Word("e".to_string())),
+            ]
+        )
+    }
+
     #[test]
     fn test_negative_numbers() {
         assert_eq!(
@@ -1,17 +1,13 @@
-use std::rc::Rc;
-
 use anyhow::Result;
 use rustyline::Editor;
 use rustyline::history::DefaultHistory;
 
 use crate::ast::Var;
-use crate::{ast, builtins, eval, parser};
+use crate::{Interpreter, ast, parser};
 
 /// The repl
 pub fn run() -> Result<()> {
-    // init scopes
-    let root_scope = builtins();
-    let mut repl_scope = root_scope.child();
+    let mut interpreter = Interpreter::new();
 
     // start reading lines
     let mut rl = rl_editor()?;
@@ -26,6 +22,11 @@ pub fn run() -> Result<()> {
         if input == "exit" || input == "quit" {
             break;
         }
+        // `?name`: what does `name` do?
+        if let Some(name) = input.strip_prefix('?') {
+            println!("{}", help(&interpreter, name.trim()));
+            continue;
+        }
         // TODO: Ctrl-C and Ctrl-D
         // TODO: autocomplete
         let s_exp = match parser::parse_text(&input) {
@@ -37,10 +38,7 @@ pub fn run() -> Result<()> {
         };
 
         // [E]val
-        let result = match eval(
-            &Var::new(ast::Expr::SExpr(s_exp)),
-            &mut repl_scope,
-        ) {
+        let result = match interpreter.eval(&Var::new(ast::Expr::SExpr(s_exp))) {
             Ok(result) => result,
             Err(err) => {
                 println!("Eval error: {err}");
@@ -55,6 +53,31 @@ pub fn run() -> Result<()> {
     Ok(())
 }
 
+/// What `name` is bound to, and its docs
+fn help(interpreter: &Interpreter, name: &str) -> String {
+    let Some(value) = interpreter.lookup(name) else {
+        return format!("'{name}' is not defined");
+    };
+    let (doc, meta) = match value.as_ref() {
+        ast::Expr::Special(special) => (Some(special.doc.to_string()), None),
+        ast::Expr::Function(func) => (func.meta.doc.clone(), Some(&func.meta)),
+        ast::Expr::Macro(mac) => (mac.meta.doc.clone(), Some(&mac.meta)),
+        _ => (None, None),
+    };
+
+    let mut lines = vec![value.to_string()];
+    lines.push(doc.unwrap_or_else(|| "(no documentation)".to_string()));
+    if let Some(meta) = meta {
+        if let Some(origin) = &meta.origin {
+            lines.push(format!("defined at {origin}"));
+        }
+        if let Some(parent) = &meta.parent {
+            lines.push(format!("defined in {parent}"));
+        }
+    }
+    lines.join("\n")
+}
+
 fn rl_editor() -> Result<Editor<(), DefaultHistory>> {
     let cfg = rustyline::Config::builder()
         .tab_stop(2)
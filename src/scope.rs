@@ -1,9 +1,8 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::iter::zip;
 use std::rc::Rc;
 
-use crate::ast::{Expr, SExpr, Var};
+use crate::ast::{Expr, Mapping, Var};
 use crate::{EResult, EvalError};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -13,28 +12,174 @@ pub struct Scope(Rc<InnerScope>);
 #[derive(Debug, PartialEq)]
 struct InnerScope {
     parent: Option<Scope>,
-    symbols: RefCell<HashMap<String, Var>>,
+    /// The kind of bindings made with `set`
+    kind: BindingKind,
+    symbols: RefCell<HashMap<String, Binding>>,
+}
+
+/// How a name was bound. Closure capture analysis uses this to tell values
+/// it has to capture from names that will only be bound when the closure
+/// runs (see `eval::bind_outer_scope`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKind {
+    /// Builtins and top-level definitions
+    Global,
+    /// Bound when a function is called or a `let` is evaluated: parameters,
+    /// local bindings and their definitions
+    Parameter,
+    /// A value captured by a closure from its enclosing scope
+    Captured,
+    /// A placeholder for a name that will be bound later, but has no value
+    /// yet
+    Local,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binding {
+    pub kind: BindingKind,
+    value: Option<Var>,
+}
+
+impl Binding {
+    /// The bound value; `None` for `Local` placeholders
+    pub fn value(&self) -> Option<&Var> {
+        self.value.as_ref()
+    }
 }
 
 impl Scope {
+    /// A root scope for globals
     pub fn new(parent: Option<Scope>) -> Self {
+        Self::with_kind(parent, BindingKind::Global)
+    }
+
+    /// A root scope for a closure's captured values
+    pub fn captures() -> Self {
+        Self::with_kind(None, BindingKind::Captured)
+    }
+
+    fn with_kind(parent: Option<Scope>, kind: BindingKind) -> Self {
         Scope(Rc::new(InnerScope {
             parent,
+            kind,
             symbols: RefCell::new(HashMap::new()),
         }))
     }
 
+    /// A child scope, whose bindings are of the same kind as this one's
     pub fn child(&self) -> Self {
-        let parent = Scope(self.0.clone());
+        Self::with_kind(Some(self.clone()), self.0.kind)
+    }
+
+    /// A child scope for a function call or `let`
+    pub fn local_child(&self) -> Self {
+        Self::with_kind(Some(self.clone()), BindingKind::Parameter)
+    }
 
-        Scope::new(Some(parent))
+    /// Bind `key` to `val`.
+    ///
+    /// Special forms (including macros) may not be aliased or shadowed, so
+    /// that closure capture analysis can always tell them apart (see
+    /// `eval::bind_outer_scope`): this fails if `val` is a special form, or
+    /// `key` names one.
+    pub fn set(&mut self, key: &str, val: Var) -> EResult<()> {
+        if let Some(special) = val.special_name() {
+            return Err(EvalError::AliasedSpecial {
+                name: key.to_string(),
+                special: special.to_string(),
+            });
+        }
+        self.check_not_special(key)?;
+        self.set_unchecked(key, val);
+        Ok(())
     }
 
-    pub fn set(&mut self, key: &str, val: Var) {
+    /// Bind `key` to `val` without enforcing the rules for special forms.
+    /// Only for registering builtins, and for capturing values that are
+    /// already bound.
+    pub(crate) fn set_unchecked(&mut self, key: &str, val: Var) {
+        self.bind(
+            key,
+            Binding {
+                kind: self.0.kind,
+                value: Some(val),
+            },
+        )
+    }
+
+    /// Declare that `key` will be bound later, shadowing any outer binding
+    pub fn declare(&mut self, key: &str) -> EResult<()> {
+        self.check_not_special(key)?;
+        self.bind(
+            key,
+            Binding {
+                kind: BindingKind::Local,
+                value: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Change the value of the innermost existing binding for `key` (`set!`).
+    ///
+    /// Fails if `key` isn't bound (or is only declared so far), or names a
+    /// special form. A dynamic variable has its value replaced instead, and
+    /// since closures capture copies of plain values, assigning to a
+    /// captured variable is an error.
+    pub fn assign(&self, key: &str, val: Var) -> EResult<()> {
+        if let Some(special) = val.special_name() {
+            return Err(EvalError::AliasedSpecial {
+                name: key.to_string(),
+                special: special.to_string(),
+            });
+        }
+
+        let mut symbols = self.0.symbols.borrow_mut();
+        let Some(binding) = symbols.get_mut(key) else {
+            drop(symbols);
+            return match &self.0.parent {
+                Some(parent) => parent.assign(key, val),
+                None => Err(EvalError::LookupError(key.to_string())),
+            };
+        };
+
+        match binding
+            .value
+            .as_ref()
+            .map(|var| var.as_ref())
+        {
+            None => Err(EvalError::LookupError(key.to_string())),
+            Some(expr) if expr.special_name().is_some() => {
+                Err(EvalError::ShadowedSpecial(key.to_string()))
+            },
+            Some(Expr::Dynamic(cell)) => {
+                cell.replace(val);
+                Ok(())
+            },
+            Some(_) if binding.kind == BindingKind::Captured => {
+                Err(EvalError::AssignedCapture(key.to_string()))
+            },
+            Some(_) => {
+                binding.value = Some(val);
+                Ok(())
+            },
+        }
+    }
+
+    fn check_not_special(&self, key: &str) -> EResult<()> {
+        match self.lookup(key) {
+            Some(var) if var.special_name().is_some() => {
+                Err(EvalError::ShadowedSpecial(key.to_string()))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn bind(&mut self, key: &str, binding: Binding) {
         self.0
             .symbols
             .borrow_mut()
-            .insert(key.to_string(), val);
+            .insert(key.to_string(), binding);
     }
 
     pub fn has(&self, symbol: &str) -> bool {
@@ -44,7 +189,12 @@ impl Scope {
             .contains_key(symbol)
     }
 
-    pub fn lookup(&self, symbol: &str) -> Option<Var> {
+    pub fn kind(&self) -> BindingKind {
+        self.0.kind
+    }
+
+    /// The innermost binding for `symbol`, if any
+    pub fn lookup_binding(&self, symbol: &str) -> Option<Binding> {
         self.0
             .symbols
             .borrow()
@@ -54,10 +204,33 @@ impl Scope {
                 self.0
                     .parent
                     .as_ref()
-                    .and_then(|parent| parent.lookup(symbol))
+                    .and_then(|parent| parent.lookup_binding(symbol))
             })
     }
 
+    /// The value bound to `symbol`; `None` if it isn't bound, or is only
+    /// declared so far
+    pub fn lookup(&self, symbol: &str) -> Option<Var> {
+        self.lookup_binding(symbol)
+            .and_then(|binding| binding.value)
+    }
+
+    /// The values bound directly in this scope (not its parents), leaving out
+    /// placeholders and special forms (including macros)
+    pub fn values(&self) -> Mapping {
+        self.0
+            .symbols
+            .borrow()
+            .iter()
+            .filter_map(|(name, binding)| {
+                binding
+                    .value()
+                    .filter(|var| var.special_name().is_none())
+                    .map(|var| (name.clone(), var.clone()))
+            })
+            .collect()
+    }
+
     /***********\
     |* Helpers *|
     \***********/
@@ -65,13 +238,4 @@ impl Scope {
         self.lookup(symbol)
             .ok_or_else(|| EvalError::LookupError(symbol.to_string()))
     }
-
-    /// Helper: Create a new scope with these arguments bound to it
-    pub fn bind_args(&self, names: &[String], values: &SExpr) -> Self {
-        let mut child_scope = self.child();
-        zip(names.iter(), values.iter()).for_each(|(name, param)| {
-            child_scope.set(name, param.clone());
-        });
-        child_scope
-    }
 }
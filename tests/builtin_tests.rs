@@ -1,14 +1,46 @@
-use lisp_playground::ast::{Expr, Value, Var};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use lisp_playground::ast::{
+    Arity, Expr, FromLisp, Function, Host, HostObject, IntoLisp, Value, Var,
+};
 use lisp_playground::parser::parse_text;
-use lisp_playground::{builtins, eval};
+use lisp_playground::{Context, EResult, EvalError, Interpreter, builtins, eval};
 
 fn parse_and_eval(s: &str) -> Var {
-    let parsed: Var = parse_text(s).unwrap().into();
+    try_eval_in_sequence(&[s]).unwrap()
+}
 
-    let root_scope = builtins();
-    let mut eval_scope = root_scope.child();
-    println!("Eval: {parsed}");
-    eval(&parsed, &mut eval_scope).unwrap()
+/// Evaluate several top-level expressions in the same scope, returning the
+/// result of the last one
+fn try_eval_in_sequence(exprs: &[&str]) -> EResult<Var> {
+    try_eval_in_context(exprs, &mut Context::new())
+}
+
+fn try_eval_in_context(exprs: &[&str], ctx: &mut Context) -> EResult<Var> {
+    let mut eval_scope = ctx.root().child();
+
+    let mut result = Var::new(Expr::empty());
+    for s in exprs {
+        let parsed: Var = parse_text(s).unwrap().into();
+        println!("Eval: {parsed}");
+        result = eval(&parsed, &mut eval_scope, ctx)?;
+    }
+    Ok(result)
+}
+
+fn assert_eval_error(exprs: &[&str], expected_msg: &str) {
+    match try_eval_in_sequence(exprs) {
+        Ok(result) => panic!("Expected an error, got {result}"),
+        Err(err) => {
+            let msg = err.to_string();
+            assert!(
+                msg.contains(expected_msg),
+                "'{msg}' does not contain '{expected_msg}'"
+            );
+        },
+    }
 }
 
 fn assert_expressions_equal(lhs: &str, rhs: &str) {
@@ -43,7 +75,7 @@ fn test_quote() {
 
     assert_var_eq(
         Value::Str("hello".to_string()),
-        sexp.get(0).unwrap(),
+        sexp.first().unwrap(),
     );
     assert_var_eq(
         Value::Str("world".to_string()),
@@ -92,3 +124,2160 @@ fn test_len() {
         "(echo 4)",
     );
 }
+
+//*** FUNCTIONS ***//
+#[test]
+fn test_rest_params() {
+    assert_expressions_equal(
+        "((lambda (a & rest) (echo rest)) 1 2 3)",
+        "(quote 2 3)",
+    );
+    assert_expressions_equal("((lambda (& xs) (echo xs)))", "(quote)");
+
+    let result = try_eval_in_sequence(&[
+        "(define (second & xs) (first (rest xs)))",
+        "(second 1 2 3)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(2), &result);
+}
+
+#[test]
+fn test_rest_params_arity() {
+    assert_eval_error(
+        &["((lambda (a b & rest) (echo a)) 1)"],
+        "takes at least 2 arguments, got 1",
+    );
+    assert_eval_error(
+        &["(lambda (a & b c) (echo a))"],
+        "exactly one name after `&`",
+    );
+    assert_eval_error(
+        &["(lambda (a & &) (echo a))"],
+        "`&opt`, `&` and `&key` once each, in that order",
+    );
+}
+
+#[test]
+fn test_optional_params() {
+    assert_expressions_equal(
+        "((lambda (a &opt (b 10)) (+ a b)) 1)",
+        "(echo 11)",
+    );
+    assert_expressions_equal(
+        "((lambda (a &opt (b 10)) (+ a b)) 1 2)",
+        "(echo 3)",
+    );
+    assert_expressions_equal("((lambda (&opt b) (echo b)))", "(echo nil)");
+
+    // defaults can refer to earlier parameters
+    assert_expressions_equal(
+        "((lambda (a &opt (b (+ a 1))) (echo b)) 5)",
+        "(echo 6)",
+    );
+    assert_eval_error(
+        &["((lambda (a &opt b) (echo a)) 1 2 3)"],
+        "takes 1 to 2 arguments, got 3",
+    );
+}
+
+#[test]
+fn test_defaults_are_captured_greedily() {
+    let result = try_eval_in_sequence(&[
+        "(define offset 100)",
+        "(define (f x &opt (y offset)) (+ x y))",
+        "(define offset 0)",
+        "(f 1)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(101), &result);
+}
+
+#[test]
+fn test_keyword_params() {
+    let greet = "(define (greet &key (:to name 'world') loud) (+ 'hello ' name))";
+    let result =
+        try_eval_in_sequence(&[greet, "(greet :to 'you' :loud true)"]).unwrap();
+    assert_var_eq(Value::Str("hello you".to_string()), &result);
+
+    let result = try_eval_in_sequence(&[greet, "(greet :loud false)"]).unwrap();
+    assert_var_eq(
+        Value::Str("hello world".to_string()),
+        &result,
+    );
+
+    assert_eval_error(
+        &[greet, "(greet)"],
+        "missing keyword argument ':loud'",
+    );
+    assert_eval_error(
+        &[greet, "(greet :loud true :name 'me')"],
+        "has no keyword argument ':name'",
+    );
+}
+
+#[test]
+fn test_signature_display() {
+    let result = try_eval_in_sequence(&[
+        "(define (f a &opt (b 1) & more &key (:ext x 2) y) (echo a))",
+        "(echo f)",
+    ])
+    .unwrap();
+    assert_eq!(
+        result.to_string(),
+        "λ f[a,[b=1],&more,:ext->x=2,:y]"
+    );
+}
+
+#[test]
+fn test_nested_special_forms() {
+    assert_expressions_equal("((lambda (x) (quote x)) 1)", "(quote x)");
+    assert_expressions_equal(
+        "(((lambda (x) (lambda (y) (+ x y))) 1) 2)",
+        "(echo 3)",
+    );
+}
+
+#[test]
+fn test_special_form_arity() {
+    assert_eval_error(
+        &["(defvar x)"],
+        "Function defvar takes 2 arguments, got 1",
+    );
+    // also checked when a lambda body is analyzed, before it's ever called
+    assert_eval_error(
+        &["(lambda (y) (defvar x))"],
+        "Function defvar takes 2 arguments, got 1",
+    );
+}
+
+#[test]
+fn test_multi_arity() {
+    let add =
+        "(define add ([x] (echo x)) ([x y] (+ x y)) ([x y & more] (echo more)))";
+    let result = try_eval_in_sequence(&[add, "(add 1)"]).unwrap();
+    assert_var_eq(Value::Int(1), &result);
+    let result = try_eval_in_sequence(&[add, "(add 1 2)"]).unwrap();
+    assert_var_eq(Value::Int(3), &result);
+    let result = try_eval_in_sequence(&[add, "(add 1 2 3)"]).unwrap();
+    assert_eq!(result.to_string(), "( 3 )");
+
+    assert_expressions_equal(
+        "((case-lambda (() (echo 0)) ((x) (echo x))))",
+        "(echo 0)",
+    );
+    let result = try_eval_in_sequence(&[add, "(echo add)"]).unwrap();
+    assert_eq!(
+        result.to_string(),
+        "λ add[x | x,y | x,y,&more]"
+    );
+
+    // the clauses can also be wrapped in a single list
+    let result = try_eval_in_sequence(&[
+        "(define add (([x] x) ([x y] (+ x y))))",
+        "(list (add 1) (add 1 2))",
+    ]);
+    assert_eq!(result.unwrap().to_string(), "( 1 3 )");
+    let result = try_eval_in_sequence(&["(define f (([x] (+ x 1))))", "(f 1)"]);
+    assert_var_eq(Value::Int(2), &result.unwrap());
+}
+
+#[test]
+fn test_multi_arity_errors() {
+    assert_eval_error(
+        &["(define f ([x] (echo x)) ([x y z] (echo x)))", "(f 1 2)"],
+        "Function f takes 1 or 3 arguments, got 2",
+    );
+    assert_eval_error(
+        &["((case-lambda ([a b & c] (echo a)) ([a &opt b] (echo a))))"],
+        "takes at least 2 or 1 to 2 arguments, got 0",
+    );
+}
+
+#[test]
+fn test_single_clause_define_is_a_value() {
+    // `([x] x)` is a call, like `((g) x)`: a single clause isn't a function
+    let result = try_eval_in_sequence(&[
+        "(define (g) (lambda (n) (+ n 1)))",
+        "(define x 1)",
+        "(define f ([g] x))",
+        "(echo f)",
+    ]);
+    assert_var_eq(Value::Int(2), &result.unwrap());
+    assert_eval_error(
+        &["(define f ([y] y))"],
+        "Could not find symbol 'y'",
+    );
+}
+
+#[test]
+fn test_destructuring_params() {
+    assert_expressions_equal(
+        "((lambda ((x y) z) (+ x z)) (quote 1 2) 3)",
+        "(echo 4)",
+    );
+    assert_expressions_equal(
+        "((lambda ((x & more)) (echo more)) (quote 1 2 3))",
+        "(quote 2 3)",
+    );
+    assert_expressions_equal(
+        "((lambda ({:keys (name) :pos (x y)}) (+ name x)) {:name 'bob' :pos \
+         ('by' 2)})",
+        "(echo 'bobby')",
+    );
+
+    let result = try_eval_in_sequence(&[
+        "(define (f {:keys (a)} [b c]) (+ a c))",
+        "(f {:a 1} (quote 2 3))",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(4), &result);
+}
+
+#[test]
+fn test_destructuring_definitions() {
+    let result =
+        try_eval_in_sequence(&["(defvar (a (b c)) (quote 1 (2 3)))", "(+ a c)"])
+            .unwrap();
+    assert_var_eq(Value::Int(4), &result);
+
+    let result =
+        try_eval_in_sequence(&["(define {:keys (a b)} {:a 1 :b 2})", "(+ a b)"])
+            .unwrap();
+    assert_var_eq(Value::Int(3), &result);
+}
+
+#[test]
+fn test_destructuring_errors() {
+    assert_eval_error(
+        &["((lambda ((x y)) (echo x)) (quote 1))"],
+        "Pattern (x y) does not match ( 1 ): expected 2 items, got 1",
+    );
+    assert_eval_error(
+        &["((lambda (a (x y)) (echo x)) 1 2)"],
+        "Pattern (x y) does not match 2: not an S-expression",
+    );
+    assert_eval_error(
+        &["(defvar {:keys (a)} {:b 1})"],
+        "missing key :a",
+    );
+}
+
+#[test]
+fn test_destructured_names_are_local() {
+    let result = try_eval_in_sequence(&[
+        "(define x 100)",
+        "(define f (lambda ((x y)) (lambda () (+ x y))))",
+        "((f (quote 1 2)))",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(3), &result);
+
+    let result = try_eval_in_sequence(&[
+        "(define a 100)",
+        "(define (g {:keys (a)}) (lambda () (echo a)))",
+        "((g {:a 1}))",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(1), &result);
+}
+
+//*** LOCAL BINDINGS ***//
+#[test]
+fn test_let() {
+    assert_expressions_equal("(let ((x 1) (y 2)) (+ x y))", "(echo 3)");
+    assert_expressions_equal(
+        "(let (((a b) (quote 1 2)) ({:keys (c)} {:c 3})) (+ a (+ b c)))",
+        "(echo 6)",
+    );
+
+    // inits are evaluated in the enclosing scope
+    let result =
+        try_eval_in_sequence(&["(define x 10)", "(let ((x 1) (y x)) (echo y))"])
+            .unwrap();
+    assert_var_eq(Value::Int(10), &result);
+
+    // bindings don't leak
+    assert_eval_error(
+        &["(let ((z 1)) (echo z))", "(echo z)"],
+        "Could not find symbol 'z'",
+    );
+}
+
+#[test]
+fn test_let_star_and_letrec() {
+    assert_expressions_equal(
+        "(let* ((x 1) (y (+ x 1))) (+ x y))",
+        "(echo 3)",
+    );
+    assert_expressions_equal(
+        "(letrec ((x 1) (y (+ x 1))) (echo y))",
+        "(echo 2)",
+    );
+    assert_eval_error(
+        &["(let ((x 1) (y (+ x 1))) (echo y))"],
+        "Could not find symbol 'x'",
+    );
+    assert_eval_error(
+        &["(let (x 1) (echo x))"],
+        "binding: (pattern init)",
+    );
+}
+
+#[test]
+fn test_closures_over_let() {
+    // the closure captures the let binding, not the later global
+    let result = try_eval_in_sequence(&[
+        "(define f (let ((x 1)) (lambda (y) (+ x y))))",
+        "(define x 100)",
+        "(f 2)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(3), &result);
+
+    // let bindings shadow outer captures, and init expressions capture greedily
+    let result = try_eval_in_sequence(&[
+        "(define x 100)",
+        "(define offset 10)",
+        "(define (make-adder x) (let* ((y (+ x offset)) (x 0)) (lambda (z) (+ x \
+         (+ y z)))))",
+        "(define offset 1000)",
+        "((make-adder 1) 5)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(16), &result);
+
+    // nested lets inside a lambda
+    assert_expressions_equal(
+        "(((lambda (a) (let ((b (+ a 1))) (let ((c (+ b 1))) (lambda () (+ a (+ \
+         b c)))))) 1))",
+        "(echo 6)",
+    );
+}
+
+//*** CONDITIONALS ***//
+#[test]
+fn test_if() {
+    assert_expressions_equal(
+        "(if (eq 1 1) (echo 'yes') (echo 'no'))",
+        "(echo 'yes')",
+    );
+    assert_expressions_equal("(if false (first 1) (echo 2))", "(echo 2)");
+    assert_eval_error(
+        &["(if 1 (echo 1) (echo 2))"],
+        "expected Bool, got Value",
+    );
+}
+
+#[test]
+fn test_cond_when_unless() {
+    let sign = "(define (sign x) (cond ((eq x 0) 'zero') ((eq x 1) 'one') (else \
+                'many')))";
+    for (arg, expected) in [("0", "zero"), ("1", "one"), ("5", "many")] {
+        let result =
+            try_eval_in_sequence(&[sign, &format!("(sign {arg})")]).unwrap();
+        assert_var_eq(Value::Str(expected.to_string()), &result);
+    }
+    assert_expressions_equal("(cond (false 1))", "(echo nil)");
+    assert_eval_error(
+        &["(cond (true))"],
+        "cond clause: (test exprs...)",
+    );
+
+    // a clause's body is evaluated in sequence, like `when`'s
+    let mut interpreter = Interpreter::with_context(Context::buffered());
+    let result = interpreter
+        .eval_str("(cond ((eq 1 1) (print 'a') 2))")
+        .unwrap();
+    assert_var_eq(Value::Int(2), &result);
+    assert_eq!(interpreter.context().take_output(), "a\n");
+
+    assert_expressions_equal("(when true (echo 1))", "(echo 1)");
+    assert_expressions_equal("(when false (first 1))", "(echo nil)");
+    assert_expressions_equal("(unless false (echo 1))", "(echo 1)");
+    assert_expressions_equal("(unless true (first 1))", "(echo nil)");
+}
+
+#[test]
+fn test_and_or() {
+    assert_expressions_equal("(and true (eq 1 1))", "(echo true)");
+    assert_expressions_equal("(and)", "(echo true)");
+    assert_expressions_equal("(or)", "(echo false)");
+    assert_expressions_equal("(or false (eq 1 2) true)", "(echo true)");
+
+    // short-circuiting: later arguments aren't evaluated
+    assert_expressions_equal("(and false undefined)", "(echo false)");
+    assert_expressions_equal("(or true (first 1))", "(echo true)");
+    assert_eval_error(&["(and true 1)"], "expected Bool, got Value");
+}
+
+#[test]
+fn test_conditionals_capture_every_branch() {
+    for (call, expected) in [("(f 0)", 1), ("(f 1)", 2), ("(f 2)", 1)] {
+        let result = try_eval_in_sequence(&[
+            "(define a 1)",
+            "(define b 2)",
+            "(define c true)",
+            "(define f (lambda (x) (cond ((eq x 0) a) ((and c (eq x 1)) b) \
+             (else (if c a b)))))",
+            "(define a 10)",
+            "(define b 20)",
+            "(define c false)",
+            call,
+        ])
+        .unwrap();
+        assert_var_eq(Value::Int(expected), &result);
+    }
+
+    let result = try_eval_in_sequence(&[
+        "(define y 1)",
+        "(define g (lambda (x) (when x (unless (and false x) y))))",
+        "(define y 2)",
+        "(g true)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(1), &result);
+}
+
+//*** SEQUENCING ***//
+#[test]
+fn test_do() {
+    assert_expressions_equal("(do (echo 1) (echo 2))", "(echo 2)");
+    assert_expressions_equal("(begin (defvar a 1) (+ a 1))", "(echo 2)");
+    assert_expressions_equal("(do)", "(echo nil)");
+}
+
+#[test]
+fn test_multi_expression_bodies() {
+    assert_expressions_equal(
+        "((lambda (x) (print 'x') (+ x 1)) 1)",
+        "(echo 2)",
+    );
+    let result = try_eval_in_sequence(&[
+        "(define (f x) (print 'called f') (+ x 1))",
+        "(f 1)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(2), &result);
+
+    assert_expressions_equal(
+        "(let ((x 1)) (print 'x') (+ x 1))",
+        "(echo 2)",
+    );
+    assert_expressions_equal(
+        "(when true (print 'one') (echo 2))",
+        "(echo 2)",
+    );
+    assert_expressions_equal(
+        "((case-lambda ((x) (print 'x') x) ((x y) y)) 1)",
+        "(echo 1)",
+    );
+    assert_eval_error(
+        &["(lambda (x))"],
+        "takes at least 2 arguments, got 1",
+    );
+}
+
+#[test]
+fn test_atom_bodies() {
+    assert_expressions_equal("((lambda (x) x) 5)", "(echo 5)");
+    assert_expressions_equal("((lambda () 'hi'))", "(echo 'hi')");
+    let result =
+        try_eval_in_sequence(&["(define (id x) x)", "(id (quote 1 2))"]).unwrap();
+    assert_eq!(result.to_string(), "( 1 2 )");
+}
+
+#[test]
+fn test_sequenced_bodies_capture_greedily() {
+    let result = try_eval_in_sequence(&[
+        "(define z 1)",
+        "(define (f x) (print 'x') (do (print 'z') (+ x z)))",
+        "(define z 100)",
+        "(f 1)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(2), &result);
+}
+
+//*** RECURSION ***//
+#[test]
+fn test_recursive_define() {
+    let sum = "(define (sum n) (if (eq n 0) 0 (+ n (sum (+ n -1)))))";
+    let result = try_eval_in_sequence(&[sum, "(sum 4)"]).unwrap();
+    assert_var_eq(Value::Int(10), &result);
+
+    // multi-arity functions can call their other clauses
+    let result = try_eval_in_sequence(&[
+        "(define count ([n] (count n 0)) ([n acc] (if (eq n 0) acc (count (+ n \
+         -1) (+ acc 1)))))",
+        "(count 3)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(3), &result);
+
+    // other free variables are still captured greedily
+    let result = try_eval_in_sequence(&[
+        "(define base 'base')",
+        "(define (down n) (if (eq n 0) base (down (+ n -1))))",
+        "(define base 'changed')",
+        "(down 3)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Str("base".to_string()), &result);
+}
+
+#[test]
+fn test_recursion_is_bound_at_definition() {
+    // redefining `f` doesn't change what the old `f` calls
+    let result = try_eval_in_sequence(&[
+        "(define (f n) (if (eq n 0) 'old' (f (+ n -1))))",
+        "(define g f)",
+        "(define (f n) 'new')",
+        "(g 2)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Str("old".to_string()), &result);
+}
+
+#[test]
+fn test_mutual_recursion() {
+    let even_odd = "(declare (even? odd?) (define (even? n) (if (eq n 0) true \
+                    (odd? (+ n -1)))) (define (odd? n) (if (eq n 0) false \
+                    (even? (+ n -1)))))";
+    let result = try_eval_in_sequence(&[even_odd, "(even? 4)"]).unwrap();
+    assert_var_eq(Value::Bool(true), &result);
+    let result = try_eval_in_sequence(&[even_odd, "(odd? 4)"]).unwrap();
+    assert_var_eq(Value::Bool(false), &result);
+
+    assert_expressions_equal(
+        "(letrec ((ev? (lambda (n) (if (eq n 0) true (od? (+ n -1))))) (od? \
+         (lambda (n) (if (eq n 0) false (ev? (+ n -1)))))) (od? 3))",
+        "(echo true)",
+    );
+
+    assert_eval_error(
+        &["(declare (a b) (define a 1))"],
+        "expected a definition for 'b'",
+    );
+}
+
+#[test]
+fn test_letrec() {
+    assert_expressions_equal(
+        "(letrec ((sum (lambda (n) (if (eq n 0) 0 (+ n (sum (+ n -1))))))) (sum \
+         3))",
+        "(echo 6)",
+    );
+    assert_expressions_equal(
+        "(letrec ((ev? (lambda (n) (if (eq n 0) true (od? (+ n -1))))) (od? \
+         (lambda (n) (if (eq n 0) false (ev? (+ n -1)))))) (ev? 4))",
+        "(echo true)",
+    );
+
+    // inits are still evaluated in order, so values can't refer forward
+    assert_eval_error(
+        &["(letrec ((a b) (b 1)) (echo a))"],
+        "Could not find symbol 'b'",
+    );
+}
+
+#[test]
+fn test_recursion_inside_closures() {
+    // an inner recursive function closing over a parameter
+    let result = try_eval_in_sequence(&[
+        "(define (repeat-add x) (letrec ((go (lambda (n) (if (eq n 0) 0 (+ x \
+         (go (+ n -1))))))) go))",
+        "(define x 1000)",
+        "((repeat-add 2) 3)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(6), &result);
+
+    let result = try_eval_in_sequence(&[
+        "(define (outer) (define (inner n) (if (eq n 0) 'done' (inner (+ n \
+         -1)))) (inner 2))",
+        "(outer)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Str("done".to_string()), &result);
+}
+
+//*** BINDING KINDS ***//
+#[test]
+fn test_capture_symbols_bound_to_themselves() {
+    // a global whose value happens to be its own symbol
+    let result = try_eval_in_sequence(&[
+        "(define x (first (quote x)))",
+        "(define f (lambda () x))",
+        "(f)",
+    ])
+    .unwrap();
+    assert_eq!(
+        result.as_ref(),
+        &Expr::Symbol("x".to_string())
+    );
+
+    // ... or a parameter
+    let result = try_eval_in_sequence(&[
+        "((lambda (x) ((lambda () x))) (first (quote x)))"
+    ])
+    .unwrap();
+    assert_eq!(
+        result.as_ref(),
+        &Expr::Symbol("x".to_string())
+    );
+
+    // quoted symbols are data, not references
+    assert_expressions_equal("((lambda (y) (quote y)) 1)", "(quote y)");
+}
+
+#[test]
+fn test_declared_names_have_no_value() {
+    assert_eval_error(
+        &["(letrec ((a b) (b 1)) a)"],
+        "Could not find symbol 'b'",
+    );
+}
+
+//*** INTERNAL DEFINITIONS ***//
+#[test]
+fn test_internal_define() {
+    // visible to later forms, and to closures created after it
+    let result = try_eval_in_sequence(&[
+        "(define (f x) (define y (+ x 1)) (define (g) (+ x y)) (g))",
+        "(f 1)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(3), &result);
+
+    // ... including closures returned to the caller
+    let result = try_eval_in_sequence(&[
+        "(define (make) (defvar (a b) (quote 1 2)) (lambda () (+ a b)))",
+        "((make))",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(3), &result);
+
+    // but they don't leak into the caller's scope
+    assert_eval_error(
+        &[
+            "(define (f) (define hidden 1) hidden)",
+            "(f)",
+            "(echo hidden)",
+        ],
+        "Could not find symbol 'hidden'",
+    );
+}
+
+#[test]
+fn test_internal_define_shadows_captures() {
+    // the local `x` shadows the global one, even in nested closures
+    let result = try_eval_in_sequence(&[
+        "(define x 100)",
+        "(define (f) (define x 1) ((lambda () x)))",
+        "(define x 200)",
+        "(f)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(1), &result);
+
+    // forms before the definition still see the captured value
+    let result = try_eval_in_sequence(&[
+        "(define x 100)",
+        "(define (f) (define old x) (define x 1) (+ old x))",
+        "(define x 200)",
+        "(f)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(101), &result);
+}
+
+#[test]
+fn test_internal_define_is_scoped_to_its_body() {
+    // a definition in a nested function doesn't hide the outer binding from
+    // the rest of the enclosing function
+    let result = try_eval_in_sequence(&[
+        "(define y 'global')",
+        "(define (f) ((lambda () (define y 'inner') y)) y)",
+        "(define y 'changed')",
+        "(f)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Str("global".to_string()), &result);
+
+    // same for `let` bodies
+    let result = try_eval_in_sequence(&[
+        "(define y 'global')",
+        "(define (f) (let () (define y 'inner')) y)",
+        "(f)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Str("global".to_string()), &result);
+}
+
+#[test]
+fn test_conditional_define_is_not_hoisted() {
+    // the definition may not run, so `x` can still be the outer one
+    let result = try_eval_in_sequence(&[
+        "(define x 'outer')",
+        "(define (f c) (when c (define x 'inner')) x)",
+        "(list (f false) (f true))",
+    ]);
+    assert_eq!(
+        result.unwrap().to_string(),
+        "( outer inner )"
+    );
+
+    // `do` (or a macro) in a body is part of that body
+    let result = try_eval_in_sequence(&[
+        "(defmacro def-one (name) (quasiquote define (unquote name) 1))",
+        "(define (g) (do (define y 1)) (def-one z) (+ y z))",
+        "(g)",
+    ]);
+    assert_var_eq(Value::Int(2), &result.unwrap());
+}
+
+//*** SPECIAL FORM RULES ***//
+#[test]
+fn test_special_forms_cannot_be_shadowed() {
+    assert_eval_error(
+        &["(define lambda 3)"],
+        "Cannot shadow special form 'lambda'",
+    );
+    assert_eval_error(
+        &["(define (quote x) x)"],
+        "Cannot shadow special form 'quote'",
+    );
+    assert_eval_error(
+        &["(lambda (if) (echo if))"],
+        "Cannot shadow special form 'if'",
+    );
+    assert_eval_error(
+        &["(let ((do 1)) (echo do))"],
+        "Cannot shadow special form 'do'",
+    );
+}
+
+#[test]
+fn test_special_forms_cannot_be_aliased() {
+    assert_eval_error(
+        &["(define q quote)"],
+        "Cannot bind special form 'quote' to 'q'",
+    );
+    assert_eval_error(
+        &["(let ((w when)) w)"],
+        "Cannot bind special form 'when' to 'w'",
+    );
+    // caught while building the closure, before it's ever called
+    assert_eval_error(
+        &["(lambda () (define q quote) (q x))"],
+        "Cannot bind special form 'quote' to 'q'",
+    );
+}
+
+#[test]
+fn test_special_forms_cannot_be_passed_or_returned() {
+    assert_eval_error(
+        &["(echo lambda)"],
+        "Cannot pass special form 'lambda' to function",
+    );
+    assert_eval_error(
+        &["(define (f) if)", "(f)"],
+        "Function f cannot return special form 'if'",
+    );
+    // calling them from functions is fine, of course
+    assert_expressions_equal("((lambda () (if true 1 2)))", "(echo 1)");
+}
+
+//*** INTROSPECTION ***//
+fn record_keys(var: &Var) -> Vec<String> {
+    let Expr::Record(mapping) = var.as_ref() else {
+        panic!("Not a record: {var}")
+    };
+    let mut keys: Vec<String> = mapping.keys().cloned().collect();
+    keys.sort();
+    keys
+}
+
+#[test]
+fn test_captures() {
+    let result = try_eval_in_sequence(&[
+        "(define x 1)",
+        "(define f (lambda (y) (if true (+ x y) y)))",
+        "(define x 2)",
+        "(captures f)",
+    ])
+    .unwrap();
+    // parameters and special forms aren't captured values
+    assert_eq!(record_keys(&result), ["+", "x"]);
+    let Expr::Record(mapping) = result.as_ref() else {
+        unreachable!()
+    };
+    // the stale value
+    assert_var_eq(Value::Int(1), &mapping["x"]);
+
+    let result = try_eval_in_sequence(&[
+        "(define (make a) (lambda () a))",
+        "(captures (make 5))",
+    ])
+    .unwrap();
+    assert_eq!(record_keys(&result), ["a"]);
+
+    let result = try_eval_in_sequence(&["(captures first)"]).unwrap();
+    assert!(record_keys(&result).is_empty());
+}
+
+#[test]
+fn test_function_captures_api() {
+    let result = try_eval_in_sequence(&[
+        "(define greeting 'hi')",
+        "(define f ([] (echo greeting)) ([x] (echo x)))",
+        "(echo f)",
+    ])
+    .unwrap();
+    let captures = result
+        .expect_fn()
+        .unwrap()
+        .captures()
+        .unwrap();
+    assert_var_eq(
+        Value::Str("hi".to_string()),
+        &captures["greeting"],
+    );
+
+    let result = try_eval_in_sequence(&["(echo first)"]).unwrap();
+    assert!(
+        result
+            .expect_fn()
+            .unwrap()
+            .captures()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_free_vars() {
+    let result = try_eval_in_sequence(&[
+        "(define x 1)",
+        "(define y 2)",
+        "(free-vars (quote + x y))",
+    ])
+    .unwrap();
+    assert_eq!(record_keys(&result), ["+", "x", "y"]);
+
+    let result = try_eval_in_sequence(&[
+        "(define x 1)",
+        "(free-vars (quote lambda (y) (let ((z y)) (+ x z))))",
+    ])
+    .unwrap();
+    assert_eq!(record_keys(&result), ["+", "x"]);
+
+    assert_eval_error(
+        &["(free-vars (quote + undefined 1))"],
+        "Could not find symbol 'undefined'",
+    );
+
+    // analyzing a definition leaves the caller's binding alone
+    let result = try_eval_in_sequence(&[
+        "(define x 5)",
+        "(free-vars (quote defvar x 1))",
+        "(echo x)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(5), &result);
+}
+
+//*** DYNAMIC VARIABLES ***//
+#[test]
+fn test_parameterize() {
+    // the closure sees the value the caller rebound it to
+    let result = try_eval_in_sequence(&[
+        "(defdynamic level 1)",
+        "(define (get-level) level)",
+        "(parameterize ((level 2)) (get-level))",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(2), &result);
+
+    // ...then the old value is restored
+    let result = try_eval_in_sequence(&[
+        "(defdynamic level 1)",
+        "(define (get-level) level)",
+        "(binding ((level 2)) (get-level))",
+        "(get-level)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(1), &result);
+
+    // nested, and all values are evaluated before any are rebound
+    let result = try_eval_in_sequence(&[
+        "(defdynamic a 1)",
+        "(defdynamic b 10)",
+        "(define (sum) (+ a b))",
+        "(parameterize ((a 2)) (parameterize ((a 3) (b a)) (sum)))",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(5), &result);
+}
+
+#[test]
+fn test_parameterize_restores_after_error() {
+    let root_scope = builtins();
+    let mut scope = root_scope.child();
+    let mut ctx = Context::new();
+    let mut eval_str = |s: &str| {
+        eval(
+            &parse_text(s).unwrap().into(),
+            &mut scope,
+            &mut ctx,
+        )
+    };
+
+    eval_str("(defdynamic level 1)").unwrap();
+    eval_str("(define (get-level) level)").unwrap();
+    assert!(eval_str("(parameterize ((level 2)) (undefined))").is_err());
+    assert_var_eq(
+        Value::Int(1),
+        &eval_str("(get-level)").unwrap(),
+    );
+}
+
+#[test]
+fn test_dynamic_variables_are_opt_in() {
+    // plain variables are still captured greedily
+    let result = try_eval_in_sequence(&[
+        "(define level 1)",
+        "(define (get-level) level)",
+        "(define level 2)",
+        "(get-level)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(1), &result);
+
+    assert_eval_error(
+        &["(define level 1)", "(parameterize ((level 2)) level)"],
+        "Dynamic",
+    );
+    assert_eval_error(
+        &["(parameterize ((level 2)) level)"],
+        "Could not find symbol 'level'",
+    );
+    assert_eval_error(
+        &["(defdynamic level if)"],
+        "Cannot bind special form",
+    );
+}
+
+#[test]
+fn test_captures_dynamic_cell() {
+    let result = try_eval_in_sequence(&[
+        "(defdynamic level 1)",
+        "(define (get-level) level)",
+        "(captures get-level)",
+    ])
+    .unwrap();
+    let Expr::Record(mapping) = result.as_ref() else {
+        panic!("Not a record: {result}")
+    };
+    assert!(matches!(
+        mapping["level"].as_ref(),
+        Expr::Dynamic(_)
+    ));
+}
+
+//*** MUTATION ***//
+#[test]
+fn test_boxes() {
+    let result = try_eval_in_sequence(&[
+        "(define b (box 1))",
+        "(set-box! b 2)",
+        "(unbox b)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(2), &result);
+
+    let result = try_eval_in_sequence(&[
+        "(define b (box 1))",
+        "(swap! b + 10)",
+        "(swap! b (lambda (n) (+ n n)))",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(22), &result);
+
+    assert_eval_error(&["(unbox 1)"], "expected Box");
+}
+
+#[test]
+fn test_captured_boxes_are_shared() {
+    // a counter
+    let result = try_eval_in_sequence(&[
+        "(define (make-counter)
+           (let ((count (box 0)))
+             (lambda () (swap! count (lambda (n) (+ n 1))))))",
+        "(define counter (make-counter))",
+        "(counter)",
+        "(counter)",
+        "(counter)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(3), &result);
+
+    // two closures sharing one box
+    let result = try_eval_in_sequence(&[
+        "(define state (box 'a'))",
+        "(define (put x) (set-box! state x))",
+        "(define (get) (unbox state))",
+        "(put 'b')",
+        "(get)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Str("b".to_string()), &result);
+}
+
+#[test]
+fn test_set() {
+    let result =
+        try_eval_in_sequence(&["(define x 1)", "(set! x (+ x 1))", "(echo x)"])
+            .unwrap();
+    assert_var_eq(Value::Int(2), &result);
+
+    // parameters and locals can be changed
+    let result = try_eval_in_sequence(&[
+        "(define (f x) (set! x (+ x 1)) (define y x) (set! y (+ y 1)) y)",
+        "(f 1)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(3), &result);
+
+    // captured plain values are copies: changing the original doesn't
+    // change the copy
+    let result = try_eval_in_sequence(&[
+        "(define x 1)",
+        "(define (get-x) x)",
+        "(set! x 2)",
+        "(get-x)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(1), &result);
+
+    // dynamic variables are shared
+    let result = try_eval_in_sequence(&[
+        "(defdynamic level 1)",
+        "(define (raise) (set! level (+ level 1)))",
+        "(raise)",
+        "(echo level)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(2), &result);
+}
+
+#[test]
+fn test_set_errors() {
+    assert_eval_error(&["(set! x 1)"], "Could not find symbol 'x'");
+    assert_eval_error(
+        &["(define x 1)", "(define (f) (set! x 2))"],
+        "Cannot set! captured variable 'x'",
+    );
+    assert_eval_error(
+        &["(set! if 1)"],
+        "Cannot shadow special form 'if'",
+    );
+    assert_eval_error(
+        &["(define x 1)", "(set! x if)"],
+        "Cannot bind special form 'if' to 'x'",
+    );
+}
+
+//*** MACROS ***//
+#[test]
+fn test_quasiquote() {
+    assert_expressions_equal(
+        "(quote a 2 (b 3 4) c)",
+        "(let ((x 2) (ys (quote 3 4))) (quasiquote a (unquote x) (b \
+         (unquote-splicing ys)) c))",
+    );
+    assert_expressions_equal("(quote 1 2)", "(list 1 (+ 1 1))");
+}
+
+#[test]
+fn test_defmacro() {
+    let result = try_eval_in_sequence(&[
+        "(defmacro my-unless (c & body)
+           (quasiquote if (unquote c) nil (do (unquote-splicing body))))",
+        "(my-unless false (print 'evaluated') 2)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(2), &result);
+
+    // arguments aren't evaluated
+    let result = try_eval_in_sequence(&[
+        "(defmacro my-unless (c & body)
+           (quasiquote if (unquote c) nil (do (unquote-splicing body))))",
+        "(my-unless true (undefined))",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Nil, &result);
+
+    // macros can expand to other macro calls
+    let result = try_eval_in_sequence(&[
+        "(defmacro twice (x) (quasiquote + (unquote x) (unquote x)))",
+        "(defmacro quadruple (x) (quasiquote twice (twice (unquote x))))",
+        "(quadruple 3)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(12), &result);
+}
+
+#[test]
+fn test_macros_in_closures() {
+    // capture analysis runs on the expansion: `x` is captured even though it
+    // only appears in the macro's output
+    let result = try_eval_in_sequence(&[
+        "(define x 10)",
+        "(defmacro add-x (e) (quasiquote + x (unquote e)))",
+        "(define (f y) (add-x y))",
+        "(define x 20)",
+        "(f 1)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(11), &result);
+
+    // ...and locals introduced by the expansion aren't captured
+    let result = try_eval_in_sequence(&[
+        "(defmacro with-one (name & body)
+           (quasiquote let (((unquote name) 1)) (unquote-splicing body)))",
+        "(define (f y) (with-one one (+ one y)))",
+        "(captures f)",
+    ])
+    .unwrap();
+    assert_eq!(record_keys(&result), ["+"]);
+
+    // bad expansions fail when the closure is created
+    assert_eval_error(
+        &[
+            "(defmacro use-z () (quasiquote + z 1))",
+            "(define (f) (use-z))",
+        ],
+        "Could not find symbol 'z'",
+    );
+}
+
+#[test]
+fn test_macroexpand() {
+    assert_expressions_equal(
+        "(quote twice (twice 3))",
+        "(do
+           (defmacro twice (x) (quasiquote + (unquote x) (unquote x)))
+           (defmacro quadruple (x) (quasiquote twice (twice (unquote x))))
+           (macroexpand-1 (quote quadruple 3)))",
+    );
+    assert_expressions_equal(
+        "(quote + (twice 3) (twice 3))",
+        "(do
+           (defmacro twice (x) (quasiquote + (unquote x) (unquote x)))
+           (defmacro quadruple (x) (quasiquote twice (twice (unquote x))))
+           (macroexpand (quote quadruple 3)))",
+    );
+    // not a macro call
+    assert_expressions_equal(
+        "(quote + 1 2)",
+        "(macroexpand (quote + 1 2))",
+    );
+}
+
+#[test]
+fn test_macro_rules() {
+    let def = "(defmacro twice (x) (quasiquote + (unquote x) (unquote x)))";
+    assert_eval_error(
+        &[def, "(define t twice)"],
+        "Cannot bind special form 'twice' to 't'",
+    );
+    assert_eval_error(
+        &[def, "(define (f twice) twice)"],
+        "Cannot shadow special form 'twice'",
+    );
+    assert_eval_error(
+        &["(defmacro if (x) x)"],
+        "Cannot shadow special form 'if'",
+    );
+    assert_eval_error(
+        &["(define (f) (defmacro m (x) x))"],
+        "defmacro at the top level",
+    );
+}
+
+//*** SYNTAX RULES ***//
+const MY_OR: &str = "(define-syntax my-or
+  (syntax-rules ()
+    ((_) false)
+    ((_ e) e)
+    ((_ e rest ...) (let ((t e)) (if t t (my-or rest ...))))))";
+
+#[test]
+fn test_syntax_rules() {
+    let result = try_eval_in_sequence(&[MY_OR, "(my-or false false 3)"]).unwrap();
+    assert_var_eq(Value::Int(3), &result);
+    let result = try_eval_in_sequence(&[MY_OR, "(my-or)"]).unwrap();
+    assert_var_eq(Value::Bool(false), &result);
+
+    // literals, and nested ellipses
+    let result = try_eval_in_sequence(&[
+        "(define-syntax my-let
+           (syntax-rules (in)
+             ((_ ((name value) ...) in body ...) ((lambda (name ...) body ...) \
+         value ...))))",
+        "(my-let ((a 1) (b 2)) in (print 'hi') (+ a b))",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(3), &result);
+
+    assert_eval_error(
+        &[
+            MY_OR,
+            "(define-syntax m (syntax-rules (in) ((_ x in y) x)))",
+            "(m 1 2 3)",
+        ],
+        "a form matching one of m's patterns",
+    );
+    assert_eval_error(
+        &["(define-syntax m (syntax-rules () ((_ x ... y ...) x)))"],
+        "at most one '...' per list",
+    );
+    assert_eval_error(
+        &[
+            "(define-syntax m (syntax-rules () ((_ x ...) x)))",
+            "(m 1 2)",
+        ],
+        "'...' after pattern variable 'x'",
+    );
+}
+
+#[test]
+fn test_syntax_rules_hygiene() {
+    // the macro's `t` doesn't capture the caller's `t`
+    let result =
+        try_eval_in_sequence(&[MY_OR, "(define t 5)", "(my-or false t)"])
+            .unwrap();
+    assert_var_eq(Value::Int(5), &result);
+
+    let result =
+        try_eval_in_sequence(&[MY_OR, "(define (f t) (my-or false t))", "(f 7)"])
+            .unwrap();
+    assert_var_eq(Value::Int(7), &result);
+
+    // ...and the caller can't change what the macro's free names refer to
+    let result = try_eval_in_sequence(&[
+        "(define (helper x) (+ x 1))",
+        "(define-syntax inc (syntax-rules () ((_ x) (helper x))))",
+        "(define (helper x) (+ x 100))",
+        "(let ((helper (lambda (x) x))) (inc 1))",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(2), &result);
+
+    // closures built from the expansion capture the caller's variables only
+    let result = try_eval_in_sequence(&[
+        "(define-syntax swap-args
+           (syntax-rules () ((_ f) (lambda (a b) (f b a)))))",
+        "(define (pair a b) (quasiquote (unquote a) (unquote b)))",
+        "(define g (swap-args pair))",
+        "(captures g)",
+    ])
+    .unwrap();
+    assert_eq!(record_keys(&result), ["pair"]);
+
+    // binders are renamed even when the name is bound where the macro is
+    // defined...
+    let result = try_eval_in_sequence(&[
+        "(define-syntax m (syntax-rules () ((_ a) (let ((first a)) first))))",
+        "(list (m 5) (first (list 6)))",
+    ])
+    .unwrap();
+    assert_eq!(result.to_string(), "( 5 6 )");
+
+    // ...while free names that weren't bound yet refer to the caller's
+    let result = try_eval_in_sequence(&[
+        "(define-syntax later (syntax-rules () ((_) (helper))))",
+        "(define (helper) 7)",
+        "(later)",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(7), &result);
+
+    // quoted data is kept as it's written, apart from pattern variables
+    let result = try_eval_in_sequence(&[
+        "(define-syntax m (syntax-rules () ((_ e) (quote sym e))))",
+        "(m 1)",
+    ])
+    .unwrap();
+    assert_eq!(result, parse_and_eval("(quote sym 1)"));
+    let result = try_eval_in_sequence(&[
+        "(define-syntax m
+           (syntax-rules () ((_ e) (quasiquote t (unquote (let ((t e)) t))))))",
+        "(define t 5)",
+        "(m 1)",
+    ])
+    .unwrap();
+    assert_eq!(result, parse_and_eval("(quote t 1)"));
+}
+
+//*** EVAL AND APPLY ***//
+#[test]
+fn test_eval() {
+    assert_expressions_equal("(echo 3)", "(eval (quote + 1 2))");
+    assert_expressions_equal("(echo 3)", "(eval (list + 1 2))");
+
+    // a fresh scope: the caller's variables aren't visible...
+    assert_eval_error(
+        &["(define x 1)", "(eval (quote + x 1))"],
+        "Could not find symbol 'x'",
+    );
+    // ...unless they're passed in
+    let result = try_eval_in_sequence(&[
+        "(define x 1)",
+        "(eval (quote + x 1) (record (list :x x)))",
+    ])
+    .unwrap();
+    assert_var_eq(Value::Int(2), &result);
+
+    // definitions don't leak out
+    assert_eval_error(
+        &["(eval (quote define y 1))", "(echo y)"],
+        "Could not find symbol 'y'",
+    );
+    assert_eval_error(&["(eval 1 2)"], "expected Record");
+
+    // the fresh scope is a child of the context's root scope
+    let mut ctx = Context::new();
+    ctx.root()
+        .clone()
+        .set("answer", Value::Int(42).into())
+        .unwrap();
+    let result =
+        try_eval_in_context(&["(eval (quote echo answer))"], &mut ctx).unwrap();
+    assert_var_eq(Value::Int(42), &result);
+}
+
+#[test]
+fn test_apply() {
+    assert_expressions_equal("(echo 3)", "(apply + (list 1 2))");
+    assert_expressions_equal(
+        "(echo 6)",
+        "(apply (lambda (a & rest) (+ a (first rest))) (list 1 5 7))",
+    );
+    assert_expressions_equal(
+        "(echo 4)",
+        "(apply (lambda (a &key b) (+ a b)) (list 1 :b 3))",
+    );
+
+    // errors are the same as for normal calls
+    assert_eval_error(
+        &["(define (f a) a)", "(apply f (list 1 2))"],
+        "Function f takes 1 arguments, got 2",
+    );
+    assert_eval_error(
+        &["(apply + (list 1 2 3))"],
+        "Function add takes 2 arguments, got 3",
+    );
+    assert_eval_error(
+        &["(apply (lambda (x) (undefined)) (list 1))"],
+        "undefined",
+    );
+    assert_eval_error(&["(apply 1 (list 1))"], "expected Function");
+}
+
+//*** CONTEXT ***//
+#[test]
+fn test_print_writes_to_context_output() {
+    let mut ctx = Context::buffered();
+    try_eval_in_context(
+        &[
+            "(print 'one')",
+            "(define (greet name) (print name))",
+            "(map greet (quote 'two' 'three'))",
+        ],
+        &mut ctx,
+    )
+    .unwrap();
+    assert_eq!(ctx.take_output(), "one\ntwo\nthree\n");
+    assert_eq!(ctx.take_output(), "");
+}
+
+//*** NATIVE FUNCTIONS ***//
+/// Evaluate `exprs` with a native `count!` function, which counts its calls
+fn eval_with_counter(exprs: &[&str]) -> (EResult<Var>, usize) {
+    let calls = Rc::new(Cell::new(0));
+    let counter = calls.clone();
+    let count = Function::native(
+        "count!",
+        &["x"],
+        Arity::Fixed(1),
+        move |_, args| {
+            counter.set(counter.get() + 1);
+            Ok(args[0].clone())
+        },
+    );
+
+    let mut scope = builtins().child();
+    scope
+        .set("count!", Expr::Function(count).into())
+        .unwrap();
+    let mut ctx = Context::new();
+    let mut result = Ok(Var::new(Expr::empty()));
+    for s in exprs {
+        result = eval(
+            &parse_text(s).unwrap().into(),
+            &mut scope,
+            &mut ctx,
+        );
+    }
+    (result, calls.get())
+}
+
+#[test]
+fn test_native_functions() {
+    let (result, calls) = eval_with_counter(&["(count! 1)", "(count! 2)"]);
+    assert_var_eq(Value::Int(2), &result.unwrap());
+    assert_eq!(calls, 2);
+
+    // anywhere a function can go
+    let (result, calls) = eval_with_counter(&[
+        "(define (twice x) (count! (count! x)))",
+        "(map twice (quote 1 2 3))",
+        "(apply count! (list 4))",
+    ]);
+    assert_var_eq(Value::Int(4), &result.unwrap());
+    assert_eq!(calls, 7);
+
+    let (result, _) = eval_with_counter(&["(echo count!)"]);
+    assert_eq!(
+        result.unwrap().to_string(),
+        "native count![x]"
+    );
+
+    let (result, calls) = eval_with_counter(&["(count! 1 2)"]);
+    assert!(
+        result
+            .unwrap_err()
+            .to_string()
+            .contains("Function count! takes 1 arguments, got 2")
+    );
+    assert_eq!(calls, 0);
+}
+
+//*** INTERPRETER ***//
+#[test]
+fn test_interpreter() {
+    let mut interpreter = Interpreter::with_context(Context::buffered());
+    let result = interpreter
+        .eval_str("(define (add3 x y z) (+ x (+ y z))) (add3 1 2 3)")
+        .unwrap();
+    assert_var_eq(Value::Int(6), &result);
+    assert_var_eq(
+        Value::Nil,
+        &interpreter.eval_str("").unwrap(),
+    );
+    assert!(interpreter.eval_str("(add3 1").is_err());
+    assert!(
+        interpreter
+            .eval_str("(add3 1 2 3))")
+            .is_err()
+    );
+
+    // top-level expressions can be atoms, too
+    assert_var_eq(
+        Value::Int(42),
+        &interpreter.eval_str("42").unwrap(),
+    );
+    assert_var_eq(
+        Value::Int(-1),
+        &interpreter
+            .eval_str("(define x 1) -1")
+            .unwrap(),
+    );
+    assert_var_eq(
+        Value::Int(1),
+        &interpreter.eval_str("x").unwrap(),
+    );
+
+    // the host can define values and call functions
+    interpreter
+        .define("ten", Value::Int(10))
+        .unwrap();
+    let result = interpreter
+        .call(
+            "add3",
+            vec![
+                Value::Int(1).into(),
+                Value::Int(2).into(),
+                Value::Int(3).into(),
+            ],
+        )
+        .unwrap();
+    assert_var_eq(Value::Int(6), &result);
+    assert_var_eq(
+        Value::Int(16),
+        &interpreter
+            .eval_str("(+ ten 6)")
+            .unwrap(),
+    );
+    assert!(interpreter.call("ten", vec![]).is_err());
+    assert!(
+        interpreter
+            .call("missing", vec![])
+            .is_err()
+    );
+
+    let path = std::env::temp_dir().join("lisp_playground_interpreter_test.lisp");
+    std::fs::write(
+        &path,
+        "(print 'from a file')\n(add3 ten ten ten)",
+    )
+    .unwrap();
+    let result = interpreter.eval_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_var_eq(Value::Int(30), &result);
+    assert_eq!(
+        interpreter.context().take_output(),
+        "from a file\n"
+    );
+}
+
+#[test]
+fn test_interpreters_are_isolated() {
+    let mut first = Interpreter::with_context(Context::buffered());
+    let mut second = Interpreter::with_context(Context::buffered());
+
+    first
+        .eval_str("(define x 1) (print 'first')")
+        .unwrap();
+    assert!(first.lookup("x").is_some());
+    assert!(second.lookup("x").is_none());
+    assert!(second.eval_str("(echo x)").is_err());
+    assert_eq!(first.context().take_output(), "first\n");
+    assert_eq!(second.context().take_output(), "");
+
+    // each interpreter names its anonymous functions from the start
+    let f = first
+        .eval_str("(lambda (x) x)")
+        .unwrap();
+    let g = second
+        .eval_str("(lambda (x) x)")
+        .unwrap();
+    assert_eq!(f.to_string(), g.to_string());
+    assert!(f.to_string().contains("λ_1"));
+}
+
+//*** CONVERSIONS ***//
+#[derive(Debug, PartialEq, FromLisp, IntoLisp)]
+struct Point {
+    x: isize,
+    y: isize,
+    #[lisp(rename = "label")]
+    name: Option<String>,
+}
+
+#[derive(Debug, PartialEq, FromLisp, IntoLisp)]
+struct Tagged<T> {
+    tag: String,
+    value: T,
+}
+
+#[test]
+fn test_conversions() {
+    let var = parse_and_eval("(list 1 2 3)");
+    assert_eq!(
+        Vec::<isize>::from_lisp(&var).unwrap(),
+        vec![1, 2, 3]
+    );
+    assert_eq!(
+        <(i32, u32, usize)>::from_lisp(&var).unwrap(),
+        (1, 2, 3)
+    );
+    assert!(<(isize, isize)>::from_lisp(&var).is_err());
+    assert_eq!(
+        Option::<bool>::from_lisp(&Value::Nil.into()).unwrap(),
+        None
+    );
+    assert_eq!(
+        u32::from_lisp(&Value::Int(-1).into())
+            .unwrap_err()
+            .to_string(),
+        "type error: expected Int in the range of u32, got -1"
+    );
+    assert_eq!(
+        Vec::<String>::from_lisp(&var)
+            .unwrap_err()
+            .to_string(),
+        "type error: expected Str, got Int"
+    );
+
+    let var = parse_and_eval("(echo {:a 1 :b 2})");
+    let map = HashMap::<String, isize>::from_lisp(&var).unwrap();
+    assert_eq!(
+        map,
+        HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)])
+    );
+    assert_eq!(record_keys(&map.into_lisp()), ["a", "b"]);
+
+    let list = vec![(1isize, "one"), (2, "two")].into_lisp();
+    assert_eq!(list.to_string(), "( ( 1 one ) ( 2 two ) )");
+
+    // integers out of the range of `isize` saturate
+    assert_var_eq(
+        Value::Int(isize::MAX),
+        &usize::MAX.into_lisp(),
+    );
+    assert_var_eq(Value::Int(7), &7usize.into_lisp());
+}
+
+#[test]
+fn test_struct_conversions() {
+    let point = Point::from_lisp(&parse_and_eval("(echo {:x 1 :y 2})")).unwrap();
+    assert_eq!(
+        point,
+        Point {
+            x: 1,
+            y: 2,
+            name: None
+        }
+    );
+
+    let var = parse_and_eval("(echo {:x 1 :y 2 :label 'origin'})");
+    let point = Point::from_lisp(&var).unwrap();
+    assert_eq!(point.name.as_deref(), Some("origin"));
+    assert_eq!(
+        record_keys(&point.into_lisp()),
+        ["label", "x", "y"]
+    );
+
+    let err = Point::from_lisp(&parse_and_eval("(echo {:x 1})")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "type error: expected Record with key :y, got Record without it"
+    );
+    assert!(Point::from_lisp(&parse_and_eval("(list 1 2)")).is_err());
+
+    // generic structs, with conversions for their type parameters
+    let var = parse_and_eval("(echo {:tag 'n' :value 3})");
+    let tagged = Tagged::<isize>::from_lisp(&var).unwrap();
+    assert_eq!(tagged.value, 3);
+    assert_eq!(tagged.into_lisp(), var);
+}
+
+#[test]
+fn test_host_functions() {
+    let mut interpreter = Interpreter::with_context(Context::buffered());
+    interpreter
+        .define(
+            "add",
+            Function::host("add", &["x", "y"], |x: isize, y: isize| {
+                x + y
+            }),
+        )
+        .unwrap();
+    interpreter
+        .define(
+            "shift",
+            Function::host(
+                "shift",
+                &["point", "dx"],
+                |p: Point, dx: isize| Point { x: p.x + dx, ..p },
+            ),
+        )
+        .unwrap();
+    interpreter
+        .define(
+            "checked-sum",
+            Function::host("checked-sum", &["xs"], |xs: Vec<isize>| {
+                xs.into_iter()
+                    .try_fold(0isize, isize::checked_add)
+                    .ok_or_else(|| {
+                        EvalError::Type {
+                            expected: "a sum that fits in an Int".to_string(),
+                            actual: "overflow".to_string(),
+                        }
+                    })
+            }),
+        )
+        .unwrap();
+
+    assert_var_eq(
+        Value::Int(3),
+        &interpreter
+            .eval_str("(add 1 2)")
+            .unwrap(),
+    );
+    let result = interpreter
+        .eval_str("(map (lambda (x) (add x 1)) (list 1 2))")
+        .unwrap();
+    assert_eq!(
+        Vec::<isize>::from_lisp(&result).unwrap(),
+        vec![2, 3]
+    );
+    let result = interpreter
+        .eval_str("(shift {:x 1 :y 2} 10)")
+        .unwrap();
+    assert_eq!(
+        Point::from_lisp(&result).unwrap(),
+        Point {
+            x: 11,
+            y: 2,
+            name: None
+        }
+    );
+    assert_var_eq(
+        Value::Int(6),
+        &interpreter
+            .eval_str("(checked-sum (list 1 2 3))")
+            .unwrap(),
+    );
+
+    let err = interpreter
+        .eval_str("(add 1 'two')")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "type error: expected Int, got Str"
+    );
+    assert!(interpreter.eval_str("(add 1)").is_err());
+    assert!(
+        interpreter
+            .eval_str("(shift (list 1 2) 1)")
+            .is_err()
+    );
+}
+
+//*** HOST OBJECTS ***//
+struct Document {
+    title: String,
+    words: RefCell<Vec<String>>,
+}
+
+struct Counter;
+
+fn document_interpreter() -> Interpreter {
+    let mut interpreter = Interpreter::with_context(Context::buffered());
+    let doc = Document {
+        title: "Notes".to_string(),
+        words: RefCell::new(Vec::new()),
+    };
+    interpreter
+        .define("notes", HostObject::new(doc))
+        .unwrap();
+    interpreter
+        .define("counter", HostObject::new(Counter))
+        .unwrap();
+    interpreter
+        .define(
+            "title",
+            Function::host("title", &["doc"], |doc: Host<Document>| {
+                doc.title.clone()
+            }),
+        )
+        .unwrap();
+    interpreter.register_method::<Document>(
+        "add-word",
+        Function::host(
+            "add-word",
+            &["doc", "word"],
+            |doc: Host<Document>, word: String| {
+                doc.words.borrow_mut().push(word);
+                doc.words.borrow().len()
+            },
+        ),
+    );
+    interpreter.register_method::<Document>(
+        "words",
+        Function::host("words", &["doc"], |doc: Host<Document>| {
+            doc.words.borrow().clone()
+        }),
+    );
+    interpreter
+}
+
+#[test]
+fn test_host_objects() {
+    let mut interpreter = document_interpreter();
+    assert_eq!(
+        interpreter
+            .eval_str("(echo notes)")
+            .unwrap()
+            .to_string(),
+        "#<Document>"
+    );
+    assert_eq!(
+        interpreter
+            .eval_str("(echo counter)")
+            .unwrap()
+            .to_string(),
+        "#<Counter>"
+    );
+    assert_var_eq(
+        Value::Str("Notes".to_string()),
+        &interpreter
+            .eval_str("(title notes)")
+            .unwrap(),
+    );
+
+    // passed through lisp code as is
+    let result = interpreter
+        .eval_str("(define (with-doc f) (f notes)) (with-doc title)")
+        .unwrap();
+    assert_var_eq(Value::Str("Notes".to_string()), &result);
+
+    // equal by identity
+    interpreter
+        .define(
+            "other",
+            HostObject::new(Document {
+                title: "Notes".to_string(),
+                words: RefCell::new(Vec::new()),
+            }),
+        )
+        .unwrap();
+    assert_var_eq(
+        Value::Bool(true),
+        &interpreter
+            .eval_str("(eq notes notes)")
+            .unwrap(),
+    );
+    assert_var_eq(
+        Value::Bool(false),
+        &interpreter
+            .eval_str("(eq notes other)")
+            .unwrap(),
+    );
+
+    // the wrong host type (or not a host object at all)
+    let err = interpreter
+        .eval_str("(title counter)")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "type error: expected #<Document>, got #<Counter>"
+    );
+    let err = interpreter
+        .eval_str("(title 'doc')")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "type error: expected #<Document>, got Str"
+    );
+}
+
+#[test]
+fn test_host_methods() {
+    let mut interpreter = document_interpreter();
+    interpreter
+        .eval_str("(send notes :add-word 'hello')")
+        .unwrap();
+    let result = interpreter
+        .eval_str("(send notes :add-word 'world')")
+        .unwrap();
+    assert_var_eq(Value::Int(2), &result);
+    let words = interpreter
+        .eval_str("(send notes :words)")
+        .unwrap();
+    assert_eq!(
+        Vec::<String>::from_lisp(&words).unwrap(),
+        ["hello", "world"]
+    );
+
+    let err = interpreter
+        .eval_str("(send counter :words)")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Host type Counter has no method 'words'"
+    );
+    assert!(
+        interpreter
+            .eval_str("(send notes :add-word)")
+            .is_err()
+    );
+    assert!(
+        interpreter
+            .eval_str("(send 'doc' :words)")
+            .is_err()
+    );
+
+    // methods are per interpreter
+    let mut other = Interpreter::with_context(Context::buffered());
+    other
+        .define("notes", interpreter.lookup("notes").unwrap())
+        .unwrap();
+    assert!(
+        other
+            .eval_str("(send notes :words)")
+            .is_err()
+    );
+}
+
+//*** PARTIAL APPLICATION ***//
+#[test]
+fn test_partial() {
+    let result = try_eval_in_sequence(&[
+        "(define (add x y) (+ x y))",
+        "(define add1 (partial add 1))",
+        "(add1 2)",
+    ]);
+    assert_var_eq(Value::Int(3), &result.unwrap());
+
+    let f =
+        try_eval_in_sequence(&["(define (add x y) (+ x y))", "(partial add 1)"])
+            .unwrap();
+    assert_eq!(f.to_string(), "partial add[x=1](y)");
+    let Expr::Function(func) = f.as_ref() else {
+        panic!("Not a function: {f}")
+    };
+    assert_eq!(func.arity, Arity::Fixed(1));
+
+    // composes with map, and with other partials
+    let result = try_eval_in_sequence(&[
+        "(define (add3 x y z) (+ x (+ y z)))",
+        "(map (partial (partial add3 1) 10) (list 1 2 3))",
+    ]);
+    assert_eq!(result.unwrap().to_string(), "( 12 13 14 )");
+    let f = try_eval_in_sequence(&[
+        "(define (add3 x y z) (+ x (+ y z)))",
+        "(partial (partial add3 1) 10)",
+    ]);
+    assert_eq!(
+        f.unwrap().to_string(),
+        "partial add3[x=1,y=10](z)"
+    );
+
+    // builtins, optional and rest parameters
+    let result = try_eval_in_sequence(&["((partial + 1) 2)"]);
+    assert_var_eq(Value::Int(3), &result.unwrap());
+    let f = try_eval_in_sequence(&[
+        "(define (f a &opt (b 2) & more) (list a b more))",
+        "(partial f 1)",
+    ]);
+    assert_eq!(
+        f.unwrap().to_string(),
+        "partial f[a=1]([b=2],&more)"
+    );
+    let result = try_eval_in_sequence(&[
+        "(define (f a &opt (b 2) & more) (list a b more))",
+        "((partial f 1 3 4) 5)",
+    ]);
+    assert_eq!(
+        result.unwrap().to_string(),
+        "( 1 3 ( 4 5 ) )"
+    );
+
+    assert_eval_error(
+        &["(define (add x y) (+ x y))", "(partial add 1 2 3)"],
+        "Function add takes 2 arguments, got 3",
+    );
+    assert_eval_error(
+        &["(define (add x y) (+ x y))", "((partial add 1) 2 3)"],
+        "Function add takes 1 arguments, got 2",
+    );
+}
+
+#[test]
+fn test_curry() {
+    let result = try_eval_in_sequence(&[
+        "(define (add3 x y z) (+ x (+ y z)))",
+        "(define c (curry add3))",
+        "(list ((c 1) 2 3) (((c 1) 2) 3) (c 1 2 3))",
+    ]);
+    assert_eq!(result.unwrap().to_string(), "( 6 6 6 )");
+
+    let f = try_eval_in_sequence(&[
+        "(define (add3 x y z) (+ x (+ y z)))",
+        "((curry add3) 1)",
+    ]);
+    assert_eq!(
+        f.unwrap().to_string(),
+        "curry add3[x=1](y,z)"
+    );
+
+    let result = try_eval_in_sequence(&[
+        "(define (add x y) (+ x y))",
+        "(map ((curry add) 10) (list 1 2))",
+    ]);
+    assert_eq!(result.unwrap().to_string(), "( 11 12 )");
+
+    assert_eval_error(
+        &["(define (add x y) (+ x y))", "((curry add) 1 2 3)"],
+        "Function add takes 2 arguments, got 3",
+    );
+
+    // keyword arguments are bound separately from the leading arguments, and
+    // the caller's keyword arguments win
+    let result = try_eval_in_sequence(&[
+        "(define (f x &key (k 0)) (list x k))",
+        "(define g ((curry f) :k 2))",
+        "(list (g 1) (g 1 :k 3) ((partial f :k 4) 1))",
+    ]);
+    assert_eq!(
+        result.unwrap().to_string(),
+        "( ( 1 2 ) ( 1 3 ) ( 1 4 ) )"
+    );
+    let f = try_eval_in_sequence(&[
+        "(define (f x y &key (k 0)) (list x y k))",
+        "((curry f) 1 :k 2)",
+    ]);
+    assert_eq!(
+        f.unwrap().to_string(),
+        "curry f[x=1,:k=2](y,:k=0)"
+    );
+}
+
+//*** METADATA ***//
+#[test]
+fn test_docstrings() {
+    let result = try_eval_in_sequence(&[
+        "(define (double x) 'Double x.' (+ x x))",
+        "(list (doc double) (double 3))",
+    ]);
+    assert_eq!(
+        result.unwrap().to_string(),
+        "( Double x. 6 )"
+    );
+
+    // a lone string is the body, not a docstring
+    let result = try_eval_in_sequence(&[
+        "(define (greeting) 'hello')",
+        "(list (doc greeting) (greeting))",
+    ]);
+    assert_eq!(result.unwrap().to_string(), "( Nil hello )");
+
+    let result = try_eval_in_sequence(&[
+        "(define sum (case-lambda 'Sum one or two numbers.' ((x) x) ((x y) (+ x \
+         y))))",
+        "(doc sum)",
+    ]);
+    assert_var_eq(
+        Value::Str("Sum one or two numbers.".to_string()),
+        &result.unwrap(),
+    );
+
+    // builtins, special forms and macros are documented too
+    let is_documented = |doc: &Var| {
+        match doc.as_ref() {
+            Expr::Value(Value::Str(doc)) => !doc.is_empty(),
+            _ => false,
+        }
+    };
+    assert!(is_documented(&parse_and_eval("(doc map)")));
+    assert!(is_documented(&parse_and_eval("(doc if)")));
+    let result = try_eval_in_sequence(&[
+        "(defmacro my-when (c & body)
+           'Evaluate body if c.'
+           (quasiquote if (unquote c) (do (unquote-splicing body)) nil))",
+        "(define-syntax my-unless
+           'Evaluate body unless c.'
+           (syntax-rules () ((_ c body ...) (if c nil (do body ...)))))",
+        "(list (doc my-when) (doc my-unless))",
+    ]);
+    assert_eq!(
+        result.unwrap().to_string(),
+        "( Evaluate body if c. Evaluate body unless c. )"
+    );
+
+    // ...including from closures
+    let result = try_eval_in_sequence(&["(define (f) (doc and))", "(f)"]);
+    assert!(is_documented(&result.unwrap()));
+
+    // `doc` is a special form, so it's a reserved name
+    assert_eval_error(
+        &["(define doc 1)"],
+        "Cannot shadow special form 'doc'",
+    );
+}
+
+#[test]
+fn test_source_and_arglist() {
+    let definition = "(define (add x y) 'Add x and y.' (+ x y))";
+    let result = try_eval_in_sequence(&[definition, "(source add)"]);
+    let expected: Var = parse_text(definition).unwrap().into();
+    assert_eq!(
+        result.unwrap().to_string(),
+        expected.to_string()
+    );
+    assert_var_eq(Value::Nil, &parse_and_eval("(source map)"));
+
+    let result = try_eval_in_sequence(&[
+        "(define (f a &opt (b 1) & more) a)",
+        "(arglist f)",
+    ]);
+    let expected: Var = parse_text("(a &opt (b 1) & more)")
+        .unwrap()
+        .into();
+    assert_eq!(
+        result.unwrap().to_string(),
+        expected.to_string()
+    );
+
+    let result = try_eval_in_sequence(&[
+        "(define g (case-lambda ((x) x) ((x y) y)))",
+        "(arglist g)",
+    ]);
+    let expected: Var = parse_text("((x) (x y))")
+        .unwrap()
+        .into();
+    assert_eq!(
+        result.unwrap().to_string(),
+        expected.to_string()
+    );
+}
+
+fn lookup_function(interpreter: &Interpreter, name: &str) -> Function {
+    let value = interpreter.lookup(name).unwrap();
+    match value.as_ref() {
+        Expr::Function(func) => func.clone(),
+        _ => panic!("Expected a function, got {value}"),
+    }
+}
+
+#[test]
+fn test_definition_origin() {
+    let mut interpreter = Interpreter::with_context(Context::buffered());
+    interpreter
+        .eval_str(
+            "(define x 1)\n\n(define (outer)\n  (define (inner) x)\n  \
+             inner)\n(define made (outer))",
+        )
+        .unwrap();
+
+    let outer = lookup_function(&interpreter, "outer");
+    let origin = outer.meta.origin.as_ref().unwrap();
+    assert_eq!(origin.to_string(), "<string>:3");
+    assert_eq!(outer.meta.parent, None);
+
+    // `inner` is created while `outer` runs, but comes from its own line
+    let inner = lookup_function(&interpreter, "made");
+    assert_eq!(inner.meta.parent.as_deref(), Some("outer"));
+    assert_eq!(
+        inner
+            .meta
+            .origin
+            .as_ref()
+            .unwrap()
+            .to_string(),
+        "<string>:4"
+    );
+
+    let mut interpreter = Interpreter::with_context(Context::buffered());
+    interpreter
+        .eval_str(
+            "(define x 1)\n\n(define (f a)\n  'docs'\n  (define (g b) b)\n  \
+             g)\n(define h (f 1))",
+        )
+        .unwrap();
+    let nested = lookup_function(&interpreter, "h");
+    let origin = nested.meta.origin.as_ref().unwrap();
+    assert_eq!(origin.to_string(), "<string>:5");
+
+    // functions defined outside of `eval_str`/`eval_file` have no origin
+    let square = parse_and_eval("(lambda (x) (+ x x))");
+    let Expr::Function(square) = square.as_ref() else {
+        panic!("Expected a function, got {square}");
+    };
+    assert!(square.meta.origin.is_none());
+}